@@ -1,48 +1,310 @@
-use std::fmt::Display;
-use std::fmt::Formatter;
+use std::cell::Cell;
+use std::rc::Rc;
 
+use crate::span::Span;
 use crate::token::Token;
+use crate::token::TokenKind;
+
+/// The number of scopes between a variable use and the scope that declares the variable, as
+/// computed by the resolver. Stays `None` for globals.
+pub(crate) type Depth = Cell<Option<usize>>;
+
+/// An identifier in the syntax tree, or one of the `this` and `super` keywords which are bound
+/// like variables. The name is owned rather than borrowed from the source, so that the tree and
+/// the functions and classes declared in it can outlive the source they were parsed from.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Name {
+    identifier: Rc<str>,
+    span: Span,
+}
+
+impl Name {
+    pub(crate) fn new(
+        identifier: impl Into<Rc<str>>,
+        span: Span,
+    ) -> Self {
+        Self {
+            identifier: identifier.into(),
+            span,
+        }
+    }
+
+    pub(crate) fn identifier(&self) -> &str {
+        &self.identifier
+    }
+
+    /// The identifier, shared rather than copied, for use as a key in maps of names.
+    pub(crate) fn shared(&self) -> Rc<str> {
+        Rc::clone(&self.identifier)
+    }
+
+    pub(crate) fn span(&self) -> Span {
+        self.span
+    }
+}
+
+impl From<Token<'_>> for Name {
+    /// The name of an identifier, `this` or `super` token.
+    fn from(token: Token) -> Self {
+        Self::new(token.identifier(), token.span())
+    }
+}
+
+/// A unary, binary or logical operator.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub(crate) struct Operator {
+    kind: OperatorKind,
+    span: Span,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub(crate) enum OperatorKind {
+    Minus,
+    Plus,
+    Slash,
+    Star,
+    Bang,
+    BangEqual,
+    EqualEqual,
+    Greater,
+    GreaterEqual,
+    Less,
+    LessEqual,
+    And,
+    Or,
+}
+
+impl Operator {
+    pub(crate) fn new(
+        kind: OperatorKind,
+        span: Span,
+    ) -> Self {
+        Self { kind, span }
+    }
+
+    pub(crate) fn kind(&self) -> OperatorKind {
+        self.kind
+    }
+
+    pub(crate) fn span(&self) -> Span {
+        self.span
+    }
+}
+
+impl OperatorKind {
+    /// The text of the operator in the source.
+    pub(crate) fn lexeme(self) -> &'static str {
+        match self {
+            OperatorKind::Minus => "-",
+            OperatorKind::Plus => "+",
+            OperatorKind::Slash => "/",
+            OperatorKind::Star => "*",
+            OperatorKind::Bang => "!",
+            OperatorKind::BangEqual => "!=",
+            OperatorKind::EqualEqual => "==",
+            OperatorKind::Greater => ">",
+            OperatorKind::GreaterEqual => ">=",
+            OperatorKind::Less => "<",
+            OperatorKind::LessEqual => "<=",
+            OperatorKind::And => "and",
+            OperatorKind::Or => "or",
+        }
+    }
+}
+
+impl From<Token<'_>> for Operator {
+    /// The operator of a token the parser has already recognized as one.
+    fn from(token: Token) -> Self {
+        let kind = match token.kind() {
+            TokenKind::Minus { .. } => OperatorKind::Minus,
+            TokenKind::Plus { .. } => OperatorKind::Plus,
+            TokenKind::Slash { .. } => OperatorKind::Slash,
+            TokenKind::Star { .. } => OperatorKind::Star,
+            TokenKind::Bang { .. } => OperatorKind::Bang,
+            TokenKind::BangEqual { .. } => OperatorKind::BangEqual,
+            TokenKind::EqualEqual { .. } => OperatorKind::EqualEqual,
+            TokenKind::Greater { .. } => OperatorKind::Greater,
+            TokenKind::GreaterEqual { .. } => OperatorKind::GreaterEqual,
+            TokenKind::Less { .. } => OperatorKind::Less,
+            TokenKind::LessEqual { .. } => OperatorKind::LessEqual,
+            TokenKind::And { .. } => OperatorKind::And,
+            TokenKind::Or { .. } => OperatorKind::Or,
+            kind => unreachable!("expected an operator, got {kind:?}"),
+        };
+        Self::new(kind, token.span())
+    }
+}
 
 #[derive(Debug)]
 #[cfg_attr(test, derive(PartialEq))]
-pub(crate) enum Expr<'a> {
+pub(crate) enum Expr {
     Binary {
-        left: Box<Expr<'a>>,
-        operator: Token<'a>,
-        right: Box<Expr<'a>>,
+        left: Box<Expr>,
+        operator: Operator,
+        right: Box<Expr>,
     },
     Unary {
-        operator: Token<'a>,
-        right: Box<Expr<'a>>,
+        operator: Operator,
+        right: Box<Expr>,
+    },
+    Logical {
+        left: Box<Expr>,
+        operator: Operator,
+        right: Box<Expr>,
     },
     Grouping {
-        expression: Box<Expr<'a>>,
+        expression: Box<Expr>,
+        span: Span,
     },
-    StringLiteral(&'a str),
-    BooleanLiteral(bool),
-    NoneLiteral,
-    NumberLiteral(f64),
+    Variable {
+        name: Name,
+        depth: Depth,
+    },
+    Assign {
+        name: Name,
+        value: Box<Expr>,
+        depth: Depth,
+    },
+    Get {
+        object: Box<Expr>,
+        name: Name,
+    },
+    Set {
+        object: Box<Expr>,
+        name: Name,
+        value: Box<Expr>,
+    },
+    This {
+        keyword: Name,
+        depth: Depth,
+    },
+    Super {
+        keyword: Name,
+        method: Name,
+        depth: Depth,
+    },
+    Call {
+        callee: Box<Expr>,
+        paren: Span,
+        arguments: Vec<Expr>,
+    },
+    StringLiteral {
+        /// The string with its escape sequences decoded, without the surrounding quotes.
+        value: Rc<str>,
+        span: Span,
+    },
+    BooleanLiteral {
+        value: bool,
+        span: Span,
+    },
+    NoneLiteral {
+        span: Span,
+    },
+    NumberLiteral {
+        value: f64,
+        span: Span,
+    },
+}
+
+impl Expr {
+    /// The source code the expression was parsed from.
+    pub(crate) fn span(&self) -> Span {
+        match self {
+            Expr::Binary { left, right, .. } | Expr::Logical { left, right, .. } => {
+                left.span().merge(right.span())
+            }
+            Expr::Unary { operator, right } => operator.span().merge(right.span()),
+            Expr::Variable { name, .. } => name.span(),
+            Expr::Assign { name, value, .. } => name.span().merge(value.span()),
+            Expr::Get { object, name } => object.span().merge(name.span()),
+            Expr::Set { object, value, .. } => object.span().merge(value.span()),
+            Expr::This { keyword, .. } => keyword.span(),
+            Expr::Super {
+                keyword, method, ..
+            } => keyword.span().merge(method.span()),
+            Expr::Call { callee, paren, .. } => callee.span().merge(*paren),
+            Expr::Grouping { span, .. }
+            | Expr::StringLiteral { span, .. }
+            | Expr::BooleanLiteral { span, .. }
+            | Expr::NoneLiteral { span }
+            | Expr::NumberLiteral { span, .. } => *span,
+        }
+    }
 }
 
 #[derive(Debug)]
-pub(crate) enum Literal<'a> {
-    Number(f64),
-    String(&'a str),
-    Boolean(bool),
-    None,
-}
-
-impl<'a> Display for Literal<'a> {
-    fn fmt(
-        &self,
-        f: &mut Formatter<'_>,
-    ) -> std::fmt::Result {
-        let v = match self {
-            Literal::Number(n) => n.to_string(),
-            Literal::String(s) => s.to_string(),
-            Literal::Boolean(b) => b.to_string(),
-            Literal::None => "Nil".to_string(),
-        };
-        write!(f, "{v}")
+#[cfg_attr(test, derive(PartialEq))]
+pub(crate) enum Stmt {
+    Expression {
+        expression: Expr,
+        span: Span,
+    },
+    Print {
+        expression: Expr,
+        span: Span,
+    },
+    Var {
+        name: Name,
+        initializer: Option<Expr>,
+        span: Span,
+    },
+    Block {
+        statements: Vec<Stmt>,
+        span: Span,
+    },
+    If {
+        condition: Expr,
+        then_branch: Box<Stmt>,
+        else_branch: Option<Box<Stmt>>,
+        span: Span,
+    },
+    While {
+        condition: Expr,
+        body: Box<Stmt>,
+        span: Span,
+    },
+    Function(Rc<FunctionDecl>),
+    Class(ClassDecl),
+    Return {
+        keyword: Span,
+        value: Option<Expr>,
+        span: Span,
+    },
+}
+
+impl Stmt {
+    /// The source code the statement was parsed from, including its terminating `;` or `}`.
+    pub(crate) fn span(&self) -> Span {
+        match self {
+            Stmt::Expression { span, .. }
+            | Stmt::Print { span, .. }
+            | Stmt::Var { span, .. }
+            | Stmt::Block { span, .. }
+            | Stmt::If { span, .. }
+            | Stmt::While { span, .. }
+            | Stmt::Return { span, .. } => *span,
+            Stmt::Function(declaration) => declaration.span,
+            Stmt::Class(declaration) => declaration.span,
+        }
     }
 }
+
+#[derive(Debug)]
+#[cfg_attr(test, derive(PartialEq))]
+pub(crate) struct ClassDecl {
+    pub(crate) name: Name,
+    pub(crate) superclass: Option<Expr>,
+    pub(crate) methods: Vec<Rc<FunctionDecl>>,
+    pub(crate) span: Span,
+}
+
+/// A function declaration, shared between the syntax tree and every function value created from
+/// it at runtime.
+#[derive(Debug)]
+#[cfg_attr(test, derive(PartialEq))]
+pub(crate) struct FunctionDecl {
+    pub(crate) name: Name,
+    pub(crate) params: Vec<Name>,
+    pub(crate) body: Vec<Stmt>,
+    pub(crate) span: Span,
+}
@@ -0,0 +1,114 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::fmt::Formatter;
+use std::rc::Rc;
+
+use crate::ast::Name;
+use crate::error::RunTimeError;
+use crate::function::Function;
+use crate::value::Value;
+
+/// The name of the method that initializes new instances of a class.
+pub(crate) const INITIALIZER: &str = "init";
+
+/// A class, with its methods and superclass.
+#[derive(Debug)]
+pub struct Class {
+    name: Rc<str>,
+    superclass: Option<Rc<Class>>,
+    methods: HashMap<Rc<str>, Rc<Function>>,
+}
+
+impl Class {
+    pub(crate) fn new(
+        name: Rc<str>,
+        superclass: Option<Rc<Class>>,
+        methods: HashMap<Rc<str>, Rc<Function>>,
+    ) -> Self {
+        Self {
+            name,
+            superclass,
+            methods,
+        }
+    }
+
+    /// Looks up the method `name` on this class and then along its superclass chain.
+    pub(crate) fn find_method(
+        &self,
+        name: &str,
+    ) -> Option<Rc<Function>> {
+        self.methods.get(name).cloned().or_else(|| {
+            self.superclass
+                .as_ref()
+                .and_then(|superclass| superclass.find_method(name))
+        })
+    }
+
+    /// The number of arguments a call to the class expects, which is that of its initializer.
+    pub(crate) fn arity(&self) -> usize {
+        self.find_method(INITIALIZER)
+            .map_or(0, |initializer| initializer.arity())
+    }
+}
+
+impl Display for Class {
+    fn fmt(
+        &self,
+        f: &mut Formatter<'_>,
+    ) -> std::fmt::Result {
+        write!(f, "{}", self.name)
+    }
+}
+
+/// An instance of a class, with its own fields.
+#[derive(Debug)]
+pub struct Instance {
+    class: Rc<Class>,
+    fields: HashMap<Rc<str>, Value>,
+}
+
+impl Instance {
+    pub(crate) fn new(class: Rc<Class>) -> Self {
+        Self {
+            class,
+            fields: HashMap::new(),
+        }
+    }
+
+    /// Looks up the property `name` of `instance`: a field if there is one, otherwise a method
+    /// of its class bound to `instance`.
+    pub(crate) fn get(
+        instance: &Rc<RefCell<Instance>>,
+        name: &Name,
+    ) -> Result<Value, RunTimeError> {
+        let this = instance.borrow();
+        if let Some(value) = this.fields.get(name.identifier()) {
+            return Ok(value.clone());
+        }
+        match this.class.find_method(name.identifier()) {
+            Some(method) => Ok(Value::Function(Rc::new(method.bind(Rc::clone(instance))))),
+            None => Err(RunTimeError::UndefinedProperty {
+                span: name.span(),
+                name: name.identifier().to_string(),
+            }),
+        }
+    }
+
+    pub(crate) fn set(
+        &mut self,
+        name: &Name,
+        value: Value,
+    ) {
+        self.fields.insert(name.shared(), value);
+    }
+}
+
+impl Display for Instance {
+    fn fmt(
+        &self,
+        f: &mut Formatter<'_>,
+    ) -> std::fmt::Result {
+        write!(f, "{} instance", self.class)
+    }
+}
@@ -0,0 +1,267 @@
+//! Conversions between Rust values and Lox values, for exchanging data with embedded programs.
+
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use crate::error::RunTimeError;
+use crate::value::Value;
+
+/// Converts a Rust value into a Lox value.
+pub trait IntoLox {
+    fn into_lox(self) -> Value;
+}
+
+/// Converts a Lox value into a Rust value, failing with [`RunTimeError::TypeMismatch`] if the
+/// value has a different type.
+///
+/// Lox values own their contents, so there is no conversion to borrowed types such as `&str`:
+/// take strings as [`Rc<str>`] to share the Lox string, or as [`String`] to copy it.
+pub trait FromLox: Sized {
+    fn from_lox(value: Value) -> Result<Self, RunTimeError>;
+}
+
+/// The result of a native function: either a value converting into a Lox value, or a `Result`
+/// of one for functions that can fail.
+pub trait IntoLoxResult {
+    fn into_lox_result(self) -> Result<Value, RunTimeError>;
+}
+
+impl<T> IntoLoxResult for T
+where
+    T: IntoLox,
+{
+    fn into_lox_result(self) -> Result<Value, RunTimeError> {
+        Ok(self.into_lox())
+    }
+}
+
+impl<T> IntoLoxResult for Result<T, RunTimeError>
+where
+    T: IntoLox,
+{
+    fn into_lox_result(self) -> Result<Value, RunTimeError> {
+        self.map(IntoLox::into_lox)
+    }
+}
+
+fn mismatch(
+    expected: &'static str,
+    found: &Value,
+) -> RunTimeError {
+    RunTimeError::type_mismatch(expected, found.type_name())
+}
+
+impl IntoLox for Value {
+    fn into_lox(self) -> Value {
+        self
+    }
+}
+
+impl FromLox for Value {
+    fn from_lox(value: Value) -> Result<Self, RunTimeError> {
+        Ok(value)
+    }
+}
+
+impl IntoLox for () {
+    fn into_lox(self) -> Value {
+        Value::None
+    }
+}
+
+impl IntoLox for f64 {
+    fn into_lox(self) -> Value {
+        Value::Number(self)
+    }
+}
+
+impl FromLox for f64 {
+    fn from_lox(value: Value) -> Result<Self, RunTimeError> {
+        match value {
+            Value::Number(n) => Ok(n),
+            value => Err(mismatch("number", &value)),
+        }
+    }
+}
+
+impl IntoLox for bool {
+    fn into_lox(self) -> Value {
+        Value::Boolean(self)
+    }
+}
+
+impl FromLox for bool {
+    fn from_lox(value: Value) -> Result<Self, RunTimeError> {
+        match value {
+            Value::Boolean(b) => Ok(b),
+            value => Err(mismatch("boolean", &value)),
+        }
+    }
+}
+
+impl IntoLox for Rc<str> {
+    fn into_lox(self) -> Value {
+        Value::String(self)
+    }
+}
+
+impl FromLox for Rc<str> {
+    fn from_lox(value: Value) -> Result<Self, RunTimeError> {
+        match value {
+            Value::String(s) => Ok(s),
+            value => Err(mismatch("string", &value)),
+        }
+    }
+}
+
+impl IntoLox for String {
+    fn into_lox(self) -> Value {
+        Value::String(self.into())
+    }
+}
+
+impl FromLox for String {
+    fn from_lox(value: Value) -> Result<Self, RunTimeError> {
+        Rc::<str>::from_lox(value).map(|s| s.to_string())
+    }
+}
+
+impl IntoLox for &str {
+    fn into_lox(self) -> Value {
+        Value::String(self.into())
+    }
+}
+
+/// `None` is `nil`.
+impl<T> IntoLox for Option<T>
+where
+    T: IntoLox,
+{
+    fn into_lox(self) -> Value {
+        self.map_or(Value::None, IntoLox::into_lox)
+    }
+}
+
+impl<T> FromLox for Option<T>
+where
+    T: FromLox,
+{
+    fn from_lox(value: Value) -> Result<Self, RunTimeError> {
+        match value {
+            Value::None => Ok(None),
+            value => T::from_lox(value).map(Some),
+        }
+    }
+}
+
+impl<T> IntoLox for Vec<T>
+where
+    T: IntoLox,
+{
+    fn into_lox(self) -> Value {
+        Value::List(self.into_iter().map(IntoLox::into_lox).collect())
+    }
+}
+
+impl<T> FromLox for Vec<T>
+where
+    T: FromLox,
+{
+    fn from_lox(value: Value) -> Result<Self, RunTimeError> {
+        match value {
+            Value::List(items) => items.iter().cloned().map(T::from_lox).collect(),
+            value => Err(mismatch("list", &value)),
+        }
+    }
+}
+
+impl<T> IntoLox for HashMap<String, T>
+where
+    T: IntoLox,
+{
+    fn into_lox(self) -> Value {
+        let entries = self
+            .into_iter()
+            .map(|(key, value)| (key, value.into_lox()))
+            .collect();
+        Value::Map(Rc::new(entries))
+    }
+}
+
+impl<T> FromLox for HashMap<String, T>
+where
+    T: FromLox,
+{
+    fn from_lox(value: Value) -> Result<Self, RunTimeError> {
+        match value {
+            Value::Map(entries) => entries
+                .iter()
+                .map(|(key, value)| Ok((key.clone(), T::from_lox(value.clone())?)))
+                .collect(),
+            value => Err(mismatch("map", &value)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip<T>(value: T) -> T
+    where
+        T: IntoLox + FromLox,
+    {
+        T::from_lox(value.into_lox()).unwrap()
+    }
+
+    #[test]
+    fn values_survive_a_round_trip() {
+        assert_eq!(round_trip(1.5), 1.5);
+        assert!(round_trip(true));
+        assert_eq!(round_trip("hi".to_string()), "hi");
+        assert_eq!(round_trip(Some(2.0)), Some(2.0));
+        assert_eq!(round_trip(None::<f64>), None);
+        assert_eq!(
+            round_trip(vec![vec![true], vec![]]),
+            vec![vec![true], vec![]]
+        );
+        let map = HashMap::from([("a".to_string(), 1.0), ("b".to_string(), 2.0)]);
+        assert_eq!(round_trip(map.clone()), map);
+    }
+
+    #[test]
+    fn lists_and_maps_print_their_contents() {
+        let map = HashMap::from([("b".to_string(), vec![1.0, 2.0]), ("a".to_string(), vec![])]);
+
+        assert_eq!(map.into_lox().to_string(), "{a: [], b: [1, 2]}");
+        assert_eq!(vec![Some("x"), None].into_lox().to_string(), "[x, Nil]");
+    }
+
+    #[test]
+    fn mismatched_types_fail() {
+        assert!(matches!(
+            f64::from_lox("1".into_lox()),
+            Err(RunTimeError::TypeMismatch {
+                expected: "number",
+                found: "string",
+                ..
+            })
+        ));
+        assert!(matches!(
+            Vec::<bool>::from_lox(vec![true.into_lox(), Value::None].into_lox()),
+            Err(RunTimeError::TypeMismatch {
+                expected: "boolean",
+                found: "nil",
+                ..
+            })
+        ));
+        assert!(matches!(
+            HashMap::<String, f64>::from_lox(Value::Number(1.0)),
+            Err(RunTimeError::TypeMismatch {
+                expected: "map",
+                found: "number",
+                ..
+            })
+        ));
+    }
+}
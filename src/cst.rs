@@ -0,0 +1,431 @@
+//! A lossless concrete syntax tree that keeps every byte of the source, including whitespace and
+//! comments, so that printing it gives back exactly the text it was built from.
+//!
+//! The tree only captures the coarse structure of a program: statements, `{ ... }` blocks and
+//! `( ... )` groups. It is built even from programs with syntax or lexical errors, which makes it
+//! suitable for formatters and refactoring tools working on code that is being edited: text that
+//! cannot be lexed becomes [`SyntaxTokenKind::Error`] tokens.
+
+use std::fmt::Display;
+use std::fmt::Formatter;
+
+use itertools::peek_nth;
+use itertools::PeekNth;
+
+use crate::lexer::span_between;
+use crate::lexer::Lexer;
+use crate::span::Span;
+use crate::token::Token;
+use crate::token::TokenKind;
+
+/// Builds the concrete syntax tree of `source`.
+pub fn parse(source: &str) -> SyntaxNode<'_> {
+    let mut builder = Builder {
+        tokens: peek_nth(lexemes(source)),
+        source,
+    };
+    builder.items(NodeKind::Program)
+}
+
+/// A token, or a piece of source that could not be lexed.
+#[derive(Copy, Clone)]
+enum Lexeme<'a> {
+    Token(Token<'a>),
+    Error(Span),
+}
+
+impl<'a> Lexeme<'a> {
+    /// The kind of the token, or `None` for text that could not be lexed.
+    fn kind(&self) -> Option<TokenKind<'a>> {
+        match self {
+            Lexeme::Token(token) => Some(token.kind()),
+            Lexeme::Error(_) => None,
+        }
+    }
+
+    fn span(&self) -> Span {
+        match self {
+            Lexeme::Token(token) => token.span(),
+            Lexeme::Error(span) => *span,
+        }
+    }
+
+    fn is_trivia(&self) -> bool {
+        self.kind().is_some_and(|kind| kind.is_trivia())
+    }
+}
+
+/// Lexes `source` including trivia. The lexer skips the text its errors are about, so every gap
+/// between two tokens becomes an error covering the skipped text.
+fn lexemes(source: &str) -> Vec<Lexeme<'_>> {
+    let mut lexemes = vec![];
+    let mut end = 0;
+    for token in Lexer::new(source).with_trivia().flatten() {
+        let span = token.span();
+        if span.start > end {
+            lexemes.push(Lexeme::Error(span_between(source, end, span.start)));
+        }
+        end = span.end;
+        if token.kind() != TokenKind::Eof {
+            lexemes.push(Lexeme::Token(token));
+        }
+    }
+    lexemes
+}
+
+/// The kinds of nodes in the concrete syntax tree.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum NodeKind {
+    /// The whole source.
+    Program,
+    /// A statement or declaration, with the comments and whitespace in front of it.
+    Statement,
+    /// A `{ ... }` block containing statements.
+    Block,
+    /// A parenthesized `( ... )` group, like call arguments or a condition.
+    Group,
+}
+
+/// The kinds of tokens in the concrete syntax tree.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum SyntaxTokenKind {
+    Whitespace,
+    Comment,
+    Keyword,
+    Identifier,
+    String,
+    Number,
+    /// Operators and delimiters.
+    Punctuation,
+    /// Text that could not be lexed, like an unexpected character or an unterminated string.
+    Error,
+}
+
+/// A node of the concrete syntax tree.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SyntaxNode<'a> {
+    kind: NodeKind,
+    children: Vec<SyntaxElement<'a>>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SyntaxElement<'a> {
+    Node(SyntaxNode<'a>),
+    Token(SyntaxToken<'a>),
+}
+
+/// A token of the concrete syntax tree, together with its text in the source.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct SyntaxToken<'a> {
+    kind: SyntaxTokenKind,
+    text: &'a str,
+    span: Span,
+}
+
+impl<'a> SyntaxNode<'a> {
+    pub fn kind(&self) -> NodeKind {
+        self.kind
+    }
+
+    pub fn children(&self) -> &[SyntaxElement<'a>] {
+        &self.children
+    }
+
+    /// Every token below this node, in source order.
+    pub fn tokens(&self) -> Box<dyn Iterator<Item = &SyntaxToken<'a>> + '_> {
+        Box::new(self.children.iter().flat_map(|child| match child {
+            SyntaxElement::Node(node) => node.tokens(),
+            SyntaxElement::Token(token) => Box::new(std::iter::once(token)),
+        }))
+    }
+}
+
+impl<'a> Display for SyntaxNode<'a> {
+    fn fmt(
+        &self,
+        f: &mut Formatter<'_>,
+    ) -> std::fmt::Result {
+        self.tokens().try_for_each(|token| f.write_str(token.text))
+    }
+}
+
+impl<'a> SyntaxToken<'a> {
+    pub fn kind(&self) -> SyntaxTokenKind {
+        self.kind
+    }
+
+    pub fn text(&self) -> &'a str {
+        self.text
+    }
+
+    pub fn span(&self) -> Span {
+        self.span
+    }
+
+    /// Whether the token is whitespace or a comment.
+    pub fn is_trivia(&self) -> bool {
+        matches!(
+            self.kind,
+            SyntaxTokenKind::Whitespace | SyntaxTokenKind::Comment
+        )
+    }
+}
+
+struct Builder<'a, I>
+where
+    I: Iterator<Item = Lexeme<'a>>,
+{
+    tokens: PeekNth<I>,
+    source: &'a str,
+}
+
+impl<'a, I> Builder<'a, I>
+where
+    I: Iterator<Item = Lexeme<'a>>,
+{
+    /// Builds a node of statements, up to the closing `}` for blocks or to the end of the source
+    /// for the program. Trivia in front of a statement becomes part of it.
+    fn items(
+        &mut self,
+        kind: NodeKind,
+    ) -> SyntaxNode<'a> {
+        let mut children = vec![];
+        let mut leading_trivia = vec![];
+        while let Some(token) = self.tokens.peek().copied() {
+            if token.is_trivia() {
+                leading_trivia.push(self.token());
+            } else if kind == NodeKind::Block
+                && matches!(token.kind(), Some(TokenKind::RightBrace { .. }))
+            {
+                break;
+            } else {
+                let statement = self.statement(kind, std::mem::take(&mut leading_trivia));
+                children.push(SyntaxElement::Node(statement));
+            }
+        }
+        children.append(&mut leading_trivia);
+        SyntaxNode { kind, children }
+    }
+
+    /// Builds a statement, which ends after a `;` or after a block that isn't followed by an
+    /// `else`, like the bodies of functions, classes and loops.
+    fn statement(
+        &mut self,
+        parent: NodeKind,
+        mut children: Vec<SyntaxElement<'a>>,
+    ) -> SyntaxNode<'a> {
+        while let Some(token) = self.tokens.peek().copied() {
+            match token.kind() {
+                Some(TokenKind::RightBrace { .. }) if parent == NodeKind::Block => break,
+                Some(TokenKind::Semicolon { .. }) => {
+                    children.push(self.token());
+                    break;
+                }
+                Some(TokenKind::LeftBrace { .. }) => {
+                    children.push(SyntaxElement::Node(self.block()));
+                    if !self.next_significant_is_else() {
+                        break;
+                    }
+                }
+                Some(TokenKind::LeftParen { .. }) => {
+                    children.push(SyntaxElement::Node(self.group()))
+                }
+                _ => children.push(self.token()),
+            }
+        }
+        SyntaxNode {
+            kind: NodeKind::Statement,
+            children,
+        }
+    }
+
+    fn block(&mut self) -> SyntaxNode<'a> {
+        let opening_brace = self.token();
+        let mut block = self.items(NodeKind::Block);
+        block.children.insert(0, opening_brace);
+        if self.tokens.peek().is_some() {
+            block.children.push(self.token());
+        }
+        block
+    }
+
+    fn group(&mut self) -> SyntaxNode<'a> {
+        let mut children = vec![self.token()];
+        while let Some(token) = self.tokens.peek().copied() {
+            match token.kind() {
+                Some(TokenKind::RightParen { .. }) => {
+                    children.push(self.token());
+                    break;
+                }
+                Some(TokenKind::LeftParen { .. }) => {
+                    children.push(SyntaxElement::Node(self.group()))
+                }
+                Some(TokenKind::LeftBrace { .. }) => {
+                    children.push(SyntaxElement::Node(self.block()))
+                }
+                _ => children.push(self.token()),
+            }
+        }
+        SyntaxNode {
+            kind: NodeKind::Group,
+            children,
+        }
+    }
+
+    fn next_significant_is_else(&mut self) -> bool {
+        let mut n = 0;
+        while self
+            .tokens
+            .peek_nth(n)
+            .is_some_and(|token| token.is_trivia())
+        {
+            n += 1;
+        }
+        self.tokens
+            .peek_nth(n)
+            .is_some_and(|token| matches!(token.kind(), Some(TokenKind::Else { .. })))
+    }
+
+    /// Consumes the next token, which must exist.
+    fn token(&mut self) -> SyntaxElement<'a> {
+        let token = self.tokens.next().expect("cannot fail");
+        let span = token.span();
+        SyntaxElement::Token(SyntaxToken {
+            kind: token
+                .kind()
+                .map_or(SyntaxTokenKind::Error, syntax_token_kind),
+            text: &self.source[span.start..span.end],
+            span,
+        })
+    }
+}
+
+fn syntax_token_kind(kind: TokenKind) -> SyntaxTokenKind {
+    match kind {
+        TokenKind::Whitespace { .. } => SyntaxTokenKind::Whitespace,
+        TokenKind::Comment { .. } => SyntaxTokenKind::Comment,
+        TokenKind::Identifier { .. } => SyntaxTokenKind::Identifier,
+        TokenKind::String { .. } => SyntaxTokenKind::String,
+        TokenKind::Number { .. } => SyntaxTokenKind::Number,
+        TokenKind::And { .. }
+        | TokenKind::Class { .. }
+        | TokenKind::Else { .. }
+        | TokenKind::False { .. }
+        | TokenKind::Fun { .. }
+        | TokenKind::For { .. }
+        | TokenKind::If { .. }
+        | TokenKind::Nil { .. }
+        | TokenKind::Or { .. }
+        | TokenKind::Print { .. }
+        | TokenKind::Return { .. }
+        | TokenKind::Super { .. }
+        | TokenKind::This { .. }
+        | TokenKind::True { .. }
+        | TokenKind::Var { .. }
+        | TokenKind::While { .. } => SyntaxTokenKind::Keyword,
+        _ => SyntaxTokenKind::Punctuation,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use proptest::prelude::*;
+
+    use super::*;
+
+    /// The kinds of the nodes below `node` and the text of each, skipping tokens.
+    fn nodes(node: &SyntaxNode) -> Vec<(NodeKind, String)> {
+        node.children()
+            .iter()
+            .filter_map(|child| match child {
+                SyntaxElement::Node(node) => Some((node.kind(), node.to_string())),
+                SyntaxElement::Token(_) => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn printing_the_tree_gives_back_the_source() {
+        let source = "// Greets.\nfun greet(name) {\n  /* nested /* comment */ */\n  print \"hi \" ;\n}\n\ngreet( \"you\" ) ;  // done\n";
+        let tree = parse(source);
+
+        assert_eq!(tree.to_string(), source);
+    }
+
+    #[test]
+    fn statements_keep_the_comments_in_front_of_them() {
+        let source = "// Says hi.\nprint \"hi\";\nif (a) { print 1; }\nelse print 2;\nvar b = 3; // trailing\n";
+        let tree = parse(source);
+
+        assert_eq!(
+            nodes(&tree),
+            vec![
+                (
+                    NodeKind::Statement,
+                    "// Says hi.\nprint \"hi\";".to_string()
+                ),
+                (
+                    NodeKind::Statement,
+                    "\nif (a) { print 1; }\nelse print 2;".to_string()
+                ),
+                (NodeKind::Statement, "\nvar b = 3;".to_string()),
+            ]
+        );
+        assert!(matches!(
+            tree.children().last(),
+            Some(SyntaxElement::Token(token)) if token.text() == "\n"
+        ));
+    }
+
+    #[test]
+    fn blocks_and_groups_become_nodes() {
+        let tree = parse("while (f(x)) { x = x - 1; }");
+        let SyntaxElement::Node(statement) = &tree.children()[0] else {
+            panic!("expected a statement");
+        };
+
+        assert_eq!(
+            nodes(statement),
+            vec![
+                (NodeKind::Group, "(f(x))".to_string()),
+                (NodeKind::Block, "{ x = x - 1; }".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn syntax_errors_still_produce_a_tree() {
+        let source = "print (1;\n}\n{ var";
+        let tree = parse(source);
+
+        assert_eq!(tree.to_string(), source);
+    }
+
+    #[test]
+    fn lexical_errors_become_error_tokens() {
+        let source = "print @;\nvar s = \"a\\qb\";\nprint \"open";
+        let tree = parse(source);
+        let errors = tree
+            .tokens()
+            .filter(|token| token.kind() == SyntaxTokenKind::Error)
+            .map(|token| (token.text(), token.span().line))
+            .collect::<Vec<_>>();
+
+        assert_eq!(tree.to_string(), source);
+        assert_eq!(errors, vec![("@", 1), ("\"a\\qb\"", 2), ("\"open", 3)]);
+    }
+
+    proptest! {
+        #[test]
+        fn any_source_round_trips(source in any::<String>()) {
+            prop_assert_eq!(parse(&source).to_string(), source);
+        }
+
+        #[test]
+        fn any_source_of_lox_tokens_round_trips(
+            source in r#"([acdf-np-wyz]{1,3}|[0-9]{1,3}|[(){};,.+=<>! \n\t-]|"[a-z ]*"|//[a-z ]*\n|/\*[a-z ]*\*/)*"#
+        ) {
+            prop_assert_eq!(parse(&source).to_string(), source);
+        }
+    }
+}
@@ -0,0 +1,225 @@
+use std::fmt::Write;
+
+use itertools::Itertools;
+
+use crate::span::Span;
+
+/// An error message pointing into the source code, rendered with the offending source lines and
+/// underlines beneath the spans it refers to.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    message: String,
+    primary: Label,
+    secondary: Vec<Label>,
+    notes: Vec<String>,
+}
+
+/// A span of source code with an optional message shown next to its underline.
+#[derive(Debug, Clone, PartialEq)]
+struct Label {
+    span: Span,
+    message: Option<String>,
+}
+
+impl Diagnostic {
+    pub(crate) fn error(
+        message: impl Into<String>,
+        span: Span,
+    ) -> Self {
+        Self {
+            message: message.into(),
+            primary: Label {
+                span,
+                message: None,
+            },
+            secondary: vec![],
+            notes: vec![],
+        }
+    }
+
+    /// Shows `message` next to the underline of the primary span.
+    pub(crate) fn with_primary_label(
+        mut self,
+        message: impl Into<String>,
+    ) -> Self {
+        self.primary.message = Some(message.into());
+        self
+    }
+
+    /// Underlines another span that helps explaining the error.
+    pub(crate) fn with_secondary_label(
+        mut self,
+        span: Span,
+        message: impl Into<String>,
+    ) -> Self {
+        self.secondary.push(Label {
+            span,
+            message: Some(message.into()),
+        });
+        self
+    }
+
+    pub(crate) fn with_note(
+        mut self,
+        note: impl Into<String>,
+    ) -> Self {
+        self.notes.push(note.into());
+        self
+    }
+
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    pub fn span(&self) -> Span {
+        self.primary.span
+    }
+
+    /// Renders the diagnostic for `source`, the contents of the file called `file_name`:
+    ///
+    /// ```text
+    /// error: expected ')' after expression
+    ///  --> script.lox:1:9
+    ///   |
+    /// 1 | print (3;
+    ///   |         ^ expected ')'
+    ///   |       - unclosed '('
+    /// ```
+    pub fn render(
+        &self,
+        file_name: &str,
+        source: &str,
+    ) -> String {
+        let lines = source.lines().collect_vec();
+        let labels = std::iter::once((&self.primary, '^'))
+            .chain(self.secondary.iter().map(|label| (label, '-')))
+            .sorted_by_key(|(label, _)| label.span.line)
+            .collect_vec();
+        let gutter_width = labels
+            .iter()
+            .map(|(label, _)| label.span.line)
+            .max()
+            .unwrap_or(1)
+            .to_string()
+            .len();
+        let gutter = " ".repeat(gutter_width);
+
+        let mut rendered = String::new();
+        let _ = writeln!(rendered, "error: {}", self.message);
+        let _ = writeln!(
+            rendered,
+            "{gutter}--> {file_name}:{}:{}",
+            self.primary.span.line, self.primary.span.column
+        );
+        let _ = writeln!(rendered, "{gutter} |");
+        let mut previous_line = None;
+        for (line_number, line_labels) in &labels.iter().chunk_by(|(label, _)| label.span.line) {
+            if previous_line.is_some_and(|previous| line_number > previous + 1) {
+                let _ = writeln!(rendered, "{gutter} ...");
+            }
+            previous_line = Some(line_number);
+            // Errors at the very end of a source ending in a newline point past its last line.
+            let line = lines
+                .get(line_number.saturating_sub(1))
+                .copied()
+                .unwrap_or_default();
+            let _ = writeln!(rendered, "{line_number:>gutter_width$} | {line}");
+            for (label, marker) in line_labels {
+                let _ = writeln!(
+                    rendered,
+                    "{gutter} | {}",
+                    underline(line, source, label, *marker).trim_end()
+                );
+            }
+        }
+        for note in &self.notes {
+            let _ = writeln!(rendered, "{gutter} = note: {note}");
+        }
+        rendered.truncate(rendered.trim_end().len());
+        rendered
+    }
+}
+
+/// Builds the row underlining `label` within `line`. Tabs before the label are kept so that the
+/// underline lines up with the source line however wide tabs are displayed.
+fn underline(
+    line: &str,
+    source: &str,
+    label: &Label,
+    marker: char,
+) -> String {
+    let indent: String = line
+        .chars()
+        .take(label.span.column.saturating_sub(1))
+        .map(|c| if c == '\t' { '\t' } else { ' ' })
+        .collect();
+    // Spans covering several lines are only underlined up to the end of their first line.
+    let width = source
+        .get(label.span.start..label.span.end)
+        .and_then(|text| text.lines().next())
+        .map_or(0, |text| text.chars().count())
+        .max(1);
+    let mut underline = format!("{indent}{}", marker.to_string().repeat(width));
+    if let Some(message) = &label.message {
+        underline.push(' ');
+        underline.push_str(message);
+    }
+    underline
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rendering_underlines_the_primary_span() {
+        let source = "var a = 1;\nprint a +;\n";
+        let diagnostic =
+            Diagnostic::error("expected expression", Span::new(20, 21, 2, 10)).with_note("a note");
+
+        assert_eq!(
+            diagnostic.render("script.lox", source),
+            "error: expected expression
+ --> script.lox:2:10
+  |
+2 | print a +;
+  |          ^
+  = note: a note"
+        );
+    }
+
+    #[test]
+    fn rendering_shows_secondary_labels_on_their_own_lines() {
+        let source = "{\n  print 1;\n\n\n\n\n\n\n\n  print 2;";
+        let diagnostic = Diagnostic::error("expected '}' after block", Span::new(30, 30, 10, 11))
+            .with_primary_label("expected '}'")
+            .with_secondary_label(Span::new(0, 1, 1, 1), "unclosed '{'");
+
+        assert_eq!(
+            diagnostic.render("script.lox", source),
+            "error: expected '}' after block
+  --> script.lox:10:11
+   |
+ 1 | {
+   | - unclosed '{'
+   ...
+10 |   print 2;
+   |           ^ expected '}'"
+        );
+    }
+
+    #[test]
+    fn rendering_keeps_tabs_in_front_of_the_underline() {
+        let source = "\tprint nope;";
+        let diagnostic = Diagnostic::error("undefined variable", Span::new(7, 11, 1, 8));
+
+        assert_eq!(
+            diagnostic.render("<stdin>", source),
+            "error: undefined variable
+ --> <stdin>:1:8
+  |
+1 | \tprint nope;
+  | \t      ^^^^"
+        );
+    }
+}
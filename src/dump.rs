@@ -0,0 +1,470 @@
+//! Printing the tokens and syntax trees the interpreter works with, for debugging the front end.
+
+use std::rc::Rc;
+
+use itertools::Itertools;
+use serde_json::json;
+use serde_json::Value;
+
+use crate::ast::ClassDecl;
+use crate::ast::Depth;
+use crate::ast::Expr;
+use crate::ast::FunctionDecl;
+use crate::ast::Name;
+use crate::ast::Operator;
+use crate::ast::Stmt;
+use crate::error::LexicalErrors;
+use crate::lexer::Lexer;
+use crate::span::Span;
+use crate::token::Token;
+use crate::visitor::ExprVisitor;
+use crate::visitor::StmtVisitor;
+
+/// How tokens and syntax trees are printed.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub enum DumpFormat {
+    /// One token or statement per line.
+    #[default]
+    Human,
+    /// A JSON array of tokens or statements.
+    Json,
+}
+
+/// Prints the tokens of `source`, with their kind, lexeme, line and span.
+pub(crate) fn tokens(
+    source: &str,
+    format: DumpFormat,
+) -> Result<String, LexicalErrors> {
+    let (tokens, errors): (Vec<_>, Vec<_>) = Lexer::new(source).partition_result();
+    if !errors.is_empty() {
+        return Err(LexicalErrors::new(errors));
+    }
+    let lexeme = |token: &Token| &source[token.span().start..token.span().end];
+    Ok(match format {
+        DumpFormat::Human => tokens
+            .iter()
+            .map(|token| {
+                let span = token.span();
+                format!(
+                    "{}:{} {}..{} {} {:?}",
+                    span.line,
+                    span.column,
+                    span.start,
+                    span.end,
+                    token.kind().name(),
+                    lexeme(token)
+                )
+            })
+            .join("\n"),
+        DumpFormat::Json => {
+            let tokens = tokens
+                .iter()
+                .map(|token| {
+                    json!({
+                        "kind": token.kind().name(),
+                        "lexeme": lexeme(token),
+                        "line": token.span().line,
+                        "column": token.span().column,
+                        "span": span(token.span()),
+                    })
+                })
+                .collect();
+            pretty(Value::Array(tokens))
+        }
+    })
+}
+
+/// Prints the syntax tree of a program, as S-expressions or as JSON.
+pub(crate) fn ast(
+    statements: &[Stmt],
+    format: DumpFormat,
+) -> String {
+    match format {
+        DumpFormat::Human => statements.iter().join("\n"),
+        DumpFormat::Json => pretty(Value::Array(
+            statements
+                .iter()
+                .map(|statement| JsonDumper.statement(statement))
+                .collect(),
+        )),
+    }
+}
+
+fn pretty(value: Value) -> String {
+    serde_json::to_string_pretty(&value).expect("JSON values always serialize")
+}
+
+fn span(span: Span) -> Value {
+    json!({ "start": span.start, "end": span.end })
+}
+
+/// Converts syntax trees to JSON objects with a `type`, a `span` and a field for each part of
+/// the node.
+struct JsonDumper;
+
+impl JsonDumper {
+    fn expression(
+        &mut self,
+        expression: &Expr,
+    ) -> Value {
+        with_span(expression.accept(self), expression.span())
+    }
+
+    fn statement(
+        &mut self,
+        statement: &Stmt,
+    ) -> Value {
+        with_span(statement.accept(self), statement.span())
+    }
+
+    fn statements(
+        &mut self,
+        statements: &[Stmt],
+    ) -> Value {
+        statements
+            .iter()
+            .map(|statement| self.statement(statement))
+            .collect()
+    }
+
+    fn function(
+        &mut self,
+        declaration: &FunctionDecl,
+    ) -> Value {
+        let value = json!({
+            "type": "Function",
+            "name": declaration.name.identifier(),
+            "params": declaration.params.iter().map(|param| param.identifier()).collect::<Vec<_>>(),
+            "body": self.statements(&declaration.body),
+        });
+        with_span(value, declaration.span)
+    }
+}
+
+fn with_span(
+    mut value: Value,
+    span: Span,
+) -> Value {
+    value["span"] = self::span(span);
+    value
+}
+
+impl ExprVisitor for JsonDumper {
+    type Output = Value;
+
+    fn visit_binary(
+        &mut self,
+        left: &Expr,
+        operator: &Operator,
+        right: &Expr,
+    ) -> Self::Output {
+        json!({
+            "type": "Binary",
+            "operator": operator.kind().lexeme(),
+            "left": self.expression(left),
+            "right": self.expression(right),
+        })
+    }
+
+    fn visit_unary(
+        &mut self,
+        operator: &Operator,
+        right: &Expr,
+    ) -> Self::Output {
+        json!({
+            "type": "Unary",
+            "operator": operator.kind().lexeme(),
+            "right": self.expression(right),
+        })
+    }
+
+    fn visit_logical(
+        &mut self,
+        left: &Expr,
+        operator: &Operator,
+        right: &Expr,
+    ) -> Self::Output {
+        json!({
+            "type": "Logical",
+            "operator": operator.kind().lexeme(),
+            "left": self.expression(left),
+            "right": self.expression(right),
+        })
+    }
+
+    fn visit_grouping(
+        &mut self,
+        expression: &Expr,
+    ) -> Self::Output {
+        json!({ "type": "Grouping", "expression": self.expression(expression) })
+    }
+
+    fn visit_variable(
+        &mut self,
+        name: &Name,
+        _depth: &Depth,
+    ) -> Self::Output {
+        json!({ "type": "Variable", "name": name.identifier() })
+    }
+
+    fn visit_assign(
+        &mut self,
+        name: &Name,
+        value: &Expr,
+        _depth: &Depth,
+    ) -> Self::Output {
+        json!({
+            "type": "Assign",
+            "name": name.identifier(),
+            "value": self.expression(value),
+        })
+    }
+
+    fn visit_get(
+        &mut self,
+        object: &Expr,
+        name: &Name,
+    ) -> Self::Output {
+        json!({
+            "type": "Get",
+            "object": self.expression(object),
+            "name": name.identifier(),
+        })
+    }
+
+    fn visit_set(
+        &mut self,
+        object: &Expr,
+        name: &Name,
+        value: &Expr,
+    ) -> Self::Output {
+        json!({
+            "type": "Set",
+            "object": self.expression(object),
+            "name": name.identifier(),
+            "value": self.expression(value),
+        })
+    }
+
+    fn visit_this(
+        &mut self,
+        _keyword: &Name,
+        _depth: &Depth,
+    ) -> Self::Output {
+        json!({ "type": "This" })
+    }
+
+    fn visit_super(
+        &mut self,
+        _keyword: &Name,
+        method: &Name,
+        _depth: &Depth,
+    ) -> Self::Output {
+        json!({ "type": "Super", "method": method.identifier() })
+    }
+
+    fn visit_call(
+        &mut self,
+        callee: &Expr,
+        _paren: Span,
+        arguments: &[Expr],
+    ) -> Self::Output {
+        json!({
+            "type": "Call",
+            "callee": self.expression(callee),
+            "arguments": arguments
+                .iter()
+                .map(|argument| self.expression(argument))
+                .collect::<Vec<_>>(),
+        })
+    }
+
+    fn visit_string_literal(
+        &mut self,
+        value: &Rc<str>,
+    ) -> Self::Output {
+        json!({ "type": "String", "value": value.as_ref() })
+    }
+
+    fn visit_boolean_literal(
+        &mut self,
+        value: bool,
+    ) -> Self::Output {
+        json!({ "type": "Boolean", "value": value })
+    }
+
+    fn visit_none_literal(&mut self) -> Self::Output {
+        json!({ "type": "Nil" })
+    }
+
+    fn visit_number_literal(
+        &mut self,
+        value: f64,
+    ) -> Self::Output {
+        json!({ "type": "Number", "value": value })
+    }
+}
+
+impl StmtVisitor for JsonDumper {
+    type Output = Value;
+
+    fn visit_expression_statement(
+        &mut self,
+        expression: &Expr,
+    ) -> Self::Output {
+        json!({ "type": "Expression", "expression": self.expression(expression) })
+    }
+
+    fn visit_print(
+        &mut self,
+        expression: &Expr,
+        _span: Span,
+    ) -> Self::Output {
+        json!({ "type": "Print", "expression": self.expression(expression) })
+    }
+
+    fn visit_var(
+        &mut self,
+        name: &Name,
+        initializer: Option<&Expr>,
+    ) -> Self::Output {
+        json!({
+            "type": "Var",
+            "name": name.identifier(),
+            "initializer": initializer.map(|initializer| self.expression(initializer)),
+        })
+    }
+
+    fn visit_block(
+        &mut self,
+        statements: &[Stmt],
+    ) -> Self::Output {
+        json!({ "type": "Block", "statements": self.statements(statements) })
+    }
+
+    fn visit_if(
+        &mut self,
+        condition: &Expr,
+        then_branch: &Stmt,
+        else_branch: Option<&Stmt>,
+    ) -> Self::Output {
+        json!({
+            "type": "If",
+            "condition": self.expression(condition),
+            "then": self.statement(then_branch),
+            "else": else_branch.map(|else_branch| self.statement(else_branch)),
+        })
+    }
+
+    fn visit_while(
+        &mut self,
+        condition: &Expr,
+        body: &Stmt,
+    ) -> Self::Output {
+        json!({
+            "type": "While",
+            "condition": self.expression(condition),
+            "body": self.statement(body),
+        })
+    }
+
+    fn visit_function(
+        &mut self,
+        declaration: &Rc<FunctionDecl>,
+    ) -> Self::Output {
+        self.function(declaration)
+    }
+
+    fn visit_class(
+        &mut self,
+        declaration: &ClassDecl,
+    ) -> Self::Output {
+        json!({
+            "type": "Class",
+            "name": declaration.name.identifier(),
+            "superclass": declaration
+                .superclass
+                .as_ref()
+                .map(|superclass| self.expression(superclass)),
+            "methods": declaration
+                .methods
+                .iter()
+                .map(|method| self.function(method))
+                .collect::<Vec<_>>(),
+        })
+    }
+
+    fn visit_return(
+        &mut self,
+        _keyword: Span,
+        value: Option<&Expr>,
+    ) -> Self::Output {
+        json!({
+            "type": "Return",
+            "value": value.map(|value| self.expression(value)),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::Parser;
+
+    #[test]
+    fn tokens_are_listed_with_their_position() {
+        let dumped = tokens("var a =\n \"hi\";", DumpFormat::Human).unwrap();
+
+        assert_eq!(
+            dumped,
+            "1:1 0..3 Var \"var\"\n\
+             1:5 4..5 Identifier \"a\"\n\
+             1:7 6..7 Equal \"=\"\n\
+             2:2 9..13 String \"\\\"hi\\\"\"\n\
+             2:6 13..14 Semicolon \";\"\n\
+             2:7 14..14 Eof \"\""
+        );
+    }
+
+    #[test]
+    fn tokens_as_json() {
+        let dumped = tokens("1.5", DumpFormat::Json).unwrap();
+        let value: Value = serde_json::from_str(&dumped).unwrap();
+
+        assert_eq!(
+            value,
+            json!([
+                { "kind": "Number", "lexeme": "1.5", "line": 1, "column": 1, "span": { "start": 0, "end": 3 } },
+                { "kind": "Eof", "lexeme": "", "line": 1, "column": 4, "span": { "start": 3, "end": 3 } },
+            ])
+        );
+    }
+
+    #[test]
+    fn lexical_errors_fail() {
+        assert!(tokens("print @;", DumpFormat::Human).is_err());
+    }
+
+    #[test]
+    fn ast_as_json() {
+        let statements = Parser::new(Lexer::new("print -x;").flatten())
+            .parse()
+            .unwrap();
+        let value: Value = serde_json::from_str(&ast(&statements, DumpFormat::Json)).unwrap();
+
+        assert_eq!(ast(&statements, DumpFormat::Human), "(print (- x))");
+        assert_eq!(
+            value,
+            json!([{
+                "type": "Print",
+                "expression": {
+                    "type": "Unary",
+                    "operator": "-",
+                    "right": { "type": "Variable", "name": "x", "span": { "start": 7, "end": 8 } },
+                    "span": { "start": 6, "end": 8 },
+                },
+                "span": { "start": 0, "end": 9 },
+            }])
+        );
+    }
+}
@@ -0,0 +1,134 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use crate::ast::Name;
+use crate::error::RunTimeError;
+use crate::value::Value;
+
+/// A single scope of variable bindings, linked to the scope it is nested in.
+#[derive(Debug, Default)]
+pub(crate) struct Environment {
+    values: HashMap<Rc<str>, Value>,
+    enclosing: Option<Rc<RefCell<Environment>>>,
+}
+
+impl Environment {
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a new scope nested inside `enclosing`.
+    pub(crate) fn with_enclosing(enclosing: Rc<RefCell<Environment>>) -> Self {
+        Self {
+            values: HashMap::new(),
+            enclosing: Some(enclosing),
+        }
+    }
+
+    /// Binds `name` to `value` in this scope, replacing any previous binding of the same name.
+    pub(crate) fn define(
+        &mut self,
+        name: Rc<str>,
+        value: Value,
+    ) {
+        self.values.insert(name, value);
+    }
+
+    /// Removes every binding of this scope.
+    pub(crate) fn clear(&mut self) {
+        self.values.clear();
+    }
+
+    /// Looks up `name` in this scope only.
+    pub(crate) fn get_own(
+        &self,
+        name: &str,
+    ) -> Option<&Value> {
+        self.values.get(name)
+    }
+
+    /// Looks up the variable `name` in this scope and then in every enclosing one.
+    pub(crate) fn get(
+        &self,
+        name: &Name,
+    ) -> Result<Value, RunTimeError> {
+        if let Some(value) = self.values.get(name.identifier()) {
+            return Ok(value.clone());
+        }
+        match &self.enclosing {
+            Some(enclosing) => enclosing.borrow().get(name),
+            None => Err(undefined_variable(name)),
+        }
+    }
+
+    /// Looks up `name` in the scope `distance` levels up the chain, as computed by the resolver.
+    ///
+    /// # Panics
+    ///
+    /// If there are fewer enclosing scopes than `distance` or the variable is not declared there.
+    pub(crate) fn get_at(
+        &self,
+        distance: usize,
+        name: &str,
+    ) -> Value {
+        if distance == 0 {
+            return self
+                .values
+                .get(name)
+                .cloned()
+                .expect("resolved variables are declared in their scope");
+        }
+        self.enclosing().borrow().get_at(distance - 1, name)
+    }
+
+    /// Assigns `value` to `name` in the scope `distance` levels up the chain, as computed by the
+    /// resolver.
+    pub(crate) fn assign_at(
+        &mut self,
+        distance: usize,
+        name: &str,
+        value: Value,
+    ) {
+        if distance == 0 {
+            let slot = self
+                .values
+                .get_mut(name)
+                .expect("resolved variables are declared in their scope");
+            *slot = value;
+        } else {
+            self.enclosing()
+                .borrow_mut()
+                .assign_at(distance - 1, name, value);
+        }
+    }
+
+    fn enclosing(&self) -> &Rc<RefCell<Environment>> {
+        self.enclosing
+            .as_ref()
+            .expect("resolved scope depths never exceed the environment chain")
+    }
+
+    /// Assigns `value` to the innermost existing binding of `name`.
+    pub(crate) fn assign(
+        &mut self,
+        name: &Name,
+        value: Value,
+    ) -> Result<(), RunTimeError> {
+        if let Some(slot) = self.values.get_mut(name.identifier()) {
+            *slot = value;
+            return Ok(());
+        }
+        match &self.enclosing {
+            Some(enclosing) => enclosing.borrow_mut().assign(name, value),
+            None => Err(undefined_variable(name)),
+        }
+    }
+}
+
+fn undefined_variable(name: &Name) -> RunTimeError {
+    RunTimeError::UndefinedVariable {
+        span: name.span(),
+        name: name.identifier().to_string(),
+    }
+}
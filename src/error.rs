@@ -1,56 +1,501 @@
+use std::fmt::Display;
+use std::fmt::Formatter;
+
+use itertools::Itertools;
 use thiserror::Error;
 
+use crate::diagnostic::Diagnostic;
+use crate::span::Span;
+
 #[derive(Debug, Error)]
 #[error(transparent)]
 pub struct PublicError(#[from] Error);
 
+impl PublicError {
+    /// The process exit code to report for this error.
+    pub fn exit_code(&self) -> u8 {
+        self.0.exit_code()
+    }
+
+    /// Renders the error followed by the chain of errors that caused it.
+    pub fn report(&self) -> String {
+        self.0.report()
+    }
+}
+
 #[derive(Debug, Error)]
 pub enum Error {
     #[error("cannot read input")]
     IO(#[from] std::io::Error),
+    #[error("cannot lex input")]
+    Lex(#[from] LexicalErrors),
     #[error("cannot parse input")]
-    Pase(#[from] ParseError),
+    Pase(#[from] ParseErrors),
+    #[error("cannot resolve input")]
+    Resolve(#[from] ResolveError),
     #[error("cannot interpret input")]
     Interpret(#[from] RunTimeError),
+    /// An error in the source code read from `file_name`, kept along with that source so the
+    /// error can be rendered as diagnostics.
+    #[error("error in {file_name}")]
+    InSource {
+        file_name: String,
+        code: String,
+        #[source]
+        error: Box<Error>,
+    },
+}
+
+impl Error {
+    /// The process exit code to report for this error, following the BSD `sysexits.h`
+    /// conventions used by the reference Lox implementations.
+    pub fn exit_code(&self) -> u8 {
+        match self {
+            Error::IO(_) => 74,
+            Error::Lex(_) | Error::Pase(_) | Error::Resolve(_) => 65,
+            Error::Interpret(_) => 70,
+            Error::InSource { error, .. } => error.exit_code(),
+        }
+    }
+
+    /// Attaches the source code the error was found in, so it is reported as diagnostics.
+    pub(crate) fn in_source(
+        self,
+        file_name: impl Into<String>,
+        code: impl Into<String>,
+    ) -> Self {
+        match self {
+            Error::IO(_) | Error::InSource { .. } => self,
+            error => Error::InSource {
+                file_name: file_name.into(),
+                code: code.into(),
+                error: Box::new(error),
+            },
+        }
+    }
+
+    /// The diagnostics pointing at the source code that caused the error, if any.
+    pub fn diagnostics(&self) -> Vec<Diagnostic> {
+        match self {
+            Error::IO(_) => vec![],
+            Error::Lex(errors) => errors
+                .errors()
+                .iter()
+                .map(LexicalError::diagnostic)
+                .collect(),
+            Error::Pase(errors) => errors.errors().iter().map(ParseError::diagnostic).collect(),
+            Error::Resolve(error) => vec![error.diagnostic()],
+            Error::Interpret(error) => vec![error.diagnostic()],
+            Error::InSource { error, .. } => error.diagnostics(),
+        }
+    }
+
+    /// Renders the error as diagnostics showing the offending source code if it is known,
+    /// otherwise as the error followed by the chain of errors that caused it.
+    pub fn report(&self) -> String {
+        if let Error::InSource {
+            file_name,
+            code,
+            error,
+        } = self
+        {
+            return error
+                .diagnostics()
+                .iter()
+                .map(|diagnostic| diagnostic.render(file_name, code))
+                .join("\n\n");
+        }
+        let mut message = self.to_string();
+        let mut source = std::error::Error::source(self);
+        while let Some(cause) = source {
+            message.push_str(&format!(": {cause}"));
+            source = cause.source();
+        }
+        message
+    }
+}
+
+/// Every lexical error found in a source, in the order they occur.
+#[derive(Debug, Error)]
+pub struct LexicalErrors(Vec<LexicalError>);
+
+impl LexicalErrors {
+    pub(crate) fn new(errors: Vec<LexicalError>) -> Self {
+        Self(errors)
+    }
+
+    pub fn errors(&self) -> &[LexicalError] {
+        &self.0
+    }
+}
+
+impl Display for LexicalErrors {
+    fn fmt(
+        &self,
+        f: &mut Formatter<'_>,
+    ) -> std::fmt::Result {
+        write!(f, "{}", self.0.iter().join("\n"))
+    }
 }
 
 #[derive(Debug, Error)]
 #[cfg_attr(test, derive(Eq, PartialEq))]
 pub enum LexicalError {
-    #[error("unexpected character {char:?} on line {line:?}")]
-    UnexpectedCharacter { char: char, line: usize },
-    #[error("unterminated string on line {line:?}")]
-    UnterminatedString { line: usize },
-    #[error("cannot parse number on line {line:?}")]
-    NaN { line: usize },
+    #[error("unexpected character {char:?}")]
+    UnexpectedCharacter { char: char, span: Span },
+    #[error("unterminated string")]
+    UnterminatedString { span: Span },
+    #[error("unterminated block comment")]
+    UnterminatedComment { span: Span },
+    #[error("invalid escape sequence '{sequence}'")]
+    InvalidEscape { sequence: String, span: Span },
+    #[error("missing digits after the number prefix")]
+    MissingDigits { span: Span },
+    #[error("missing digits in the exponent")]
+    MissingExponentDigits { span: Span },
+    #[error("invalid digit {digit:?} in a base {radix} number")]
+    InvalidDigit { digit: char, radix: u32, span: Span },
+    #[error("misplaced digit separator")]
+    MisplacedDigitSeparator { span: Span },
+}
+
+impl LexicalError {
+    pub fn span(&self) -> Span {
+        match self {
+            LexicalError::UnexpectedCharacter { span, .. }
+            | LexicalError::UnterminatedString { span }
+            | LexicalError::UnterminatedComment { span }
+            | LexicalError::InvalidEscape { span, .. }
+            | LexicalError::MissingDigits { span }
+            | LexicalError::MissingExponentDigits { span }
+            | LexicalError::InvalidDigit { span, .. }
+            | LexicalError::MisplacedDigitSeparator { span } => *span,
+        }
+    }
+
+    pub fn diagnostic(&self) -> Diagnostic {
+        let diagnostic = Diagnostic::error(self.to_string(), self.span());
+        match self {
+            LexicalError::UnterminatedString { .. } => {
+                diagnostic.with_note("strings must end with a closing '\"'")
+            }
+            LexicalError::UnterminatedComment { .. } => {
+                diagnostic.with_note("block comments nest, so every '/*' needs its own '*/'")
+            }
+            LexicalError::InvalidEscape { .. } => diagnostic.with_note(
+                "supported escapes are \\n, \\t, \\\", \\\\ and \\u{...}; \
+                 raw strings like r\"...\" keep backslashes as they are",
+            ),
+            LexicalError::MisplacedDigitSeparator { .. } => {
+                diagnostic.with_note("'_' may only separate two digits, as in 1_000_000")
+            }
+            _ => diagnostic,
+        }
+    }
+}
+
+/// Every syntax error found in a source, in the order they occur.
+#[derive(Debug, Error)]
+pub struct ParseErrors(Vec<ParseError>);
+
+impl ParseErrors {
+    pub(crate) fn new(errors: Vec<ParseError>) -> Self {
+        Self(errors)
+    }
+
+    pub fn errors(&self) -> &[ParseError] {
+        &self.0
+    }
+}
+
+impl Display for ParseErrors {
+    fn fmt(
+        &self,
+        f: &mut Formatter<'_>,
+    ) -> std::fmt::Result {
+        write!(f, "{}", self.0.iter().join("\n"))
+    }
 }
 
 #[derive(Debug, Error)]
 pub enum ParseError {
-    #[error("expected ')' after expression on line {line:?}")]
-    ExpectedClosingParenAfterExpr { line: usize },
+    #[error("expected ')' after expression")]
+    ExpectedClosingParenAfterExpr { span: Span, opening: Span },
+    #[error("expected '(' after '{keyword}'")]
+    ExpectedOpeningParenAfterKeyword { keyword: &'static str, span: Span },
+    #[error("expected ')' after condition")]
+    ExpectedClosingParenAfterCondition { span: Span },
+    #[error("expected ')' after for clauses")]
+    ExpectedClosingParenAfterForClauses { span: Span },
+    #[error("expected ';' after loop condition")]
+    ExpectedSemicolonAfterLoopCondition { span: Span },
+    #[error("expected ';' after value")]
+    ExpectedSemicolonAfterValue { span: Span },
+    #[error("expected ';' after expression")]
+    ExpectedSemicolonAfterExpr { span: Span },
+    #[error("expected ';' after variable declaration")]
+    ExpectedSemicolonAfterVarDecl { span: Span },
+    #[error("expected '}}' after block")]
+    ExpectedClosingBraceAfterBlock { span: Span, opening: Span },
+    #[error("expected class name")]
+    ExpectedClassName { span: Span },
+    #[error("expected superclass name")]
+    ExpectedSuperclassName { span: Span },
+    #[error("a class cannot inherit from itself")]
+    ClassInheritsFromItself { span: Span, class_name: Span },
+    #[error("expected '.' after 'super'")]
+    ExpectedDotAfterSuper { span: Span },
+    #[error("expected superclass method name")]
+    ExpectedSuperclassMethodName { span: Span },
+    #[error("expected '{{' before class body")]
+    ExpectedOpeningBraceBeforeClassBody { span: Span },
+    #[error("expected '}}' after class body")]
+    ExpectedClosingBraceAfterClassBody { span: Span },
+    #[error("expected property name after '.'")]
+    ExpectedPropertyName { span: Span },
+    #[error("expected function name")]
+    ExpectedFunctionName { span: Span },
+    #[error("expected '(' after function name")]
+    ExpectedOpeningParenAfterFunctionName { span: Span },
+    #[error("expected parameter name")]
+    ExpectedParameterName { span: Span },
+    #[error("expected ')' after parameters")]
+    ExpectedClosingParenAfterParameters { span: Span },
+    #[error("expected '{{' before function body")]
+    ExpectedOpeningBraceBeforeFunctionBody { span: Span },
+    #[error("expected ')' after arguments")]
+    ExpectedClosingParenAfterArguments { span: Span, opening: Span },
+    #[error("expected ';' after return value")]
+    ExpectedSemicolonAfterReturnValue { span: Span },
+    #[error("cannot have more than {max} parameters")]
+    TooManyParameters { max: usize, span: Span },
+    #[error("cannot have more than {max} arguments")]
+    TooManyArguments { max: usize, span: Span },
+    #[error("cannot nest more than {max} levels deep")]
+    TooDeeplyNested { max: usize, span: Span },
+    #[error("expected variable name")]
+    ExpectedVariableName { span: Span },
+    #[error("invalid assignment target")]
+    InvalidAssignmentTarget { span: Span },
     #[error("unexpected EOF")]
-    UnexpectedEof,
+    UnexpectedEof { span: Span },
     #[error("expected expression")]
-    ExpectExpression,
-    #[error("internal parser error")]
-    Internal(#[from] ParseErrorInternal),
+    ExpectExpression { span: Span },
+}
+
+impl ParseError {
+    pub fn span(&self) -> Span {
+        match self {
+            ParseError::ExpectedClosingParenAfterExpr { span, .. }
+            | ParseError::ExpectedOpeningParenAfterKeyword { span, .. }
+            | ParseError::ExpectedClosingParenAfterCondition { span }
+            | ParseError::ExpectedClosingParenAfterForClauses { span }
+            | ParseError::ExpectedSemicolonAfterLoopCondition { span }
+            | ParseError::ExpectedSemicolonAfterValue { span }
+            | ParseError::ExpectedSemicolonAfterExpr { span }
+            | ParseError::ExpectedSemicolonAfterVarDecl { span }
+            | ParseError::ExpectedClosingBraceAfterBlock { span, .. }
+            | ParseError::ExpectedClassName { span }
+            | ParseError::ExpectedSuperclassName { span }
+            | ParseError::ClassInheritsFromItself { span, .. }
+            | ParseError::ExpectedDotAfterSuper { span }
+            | ParseError::ExpectedSuperclassMethodName { span }
+            | ParseError::ExpectedOpeningBraceBeforeClassBody { span }
+            | ParseError::ExpectedClosingBraceAfterClassBody { span }
+            | ParseError::ExpectedPropertyName { span }
+            | ParseError::ExpectedFunctionName { span }
+            | ParseError::ExpectedOpeningParenAfterFunctionName { span }
+            | ParseError::ExpectedParameterName { span }
+            | ParseError::ExpectedClosingParenAfterParameters { span }
+            | ParseError::ExpectedOpeningBraceBeforeFunctionBody { span }
+            | ParseError::ExpectedClosingParenAfterArguments { span, .. }
+            | ParseError::ExpectedSemicolonAfterReturnValue { span }
+            | ParseError::TooManyParameters { span, .. }
+            | ParseError::TooManyArguments { span, .. }
+            | ParseError::TooDeeplyNested { span, .. }
+            | ParseError::ExpectedVariableName { span }
+            | ParseError::InvalidAssignmentTarget { span }
+            | ParseError::UnexpectedEof { span }
+            | ParseError::ExpectExpression { span } => *span,
+        }
+    }
+
+    pub fn diagnostic(&self) -> Diagnostic {
+        let diagnostic = Diagnostic::error(self.to_string(), self.span());
+        match self {
+            ParseError::ExpectedClosingParenAfterExpr { opening, .. }
+            | ParseError::ExpectedClosingParenAfterArguments { opening, .. } => diagnostic
+                .with_primary_label("expected ')'")
+                .with_secondary_label(*opening, "unclosed '('"),
+            ParseError::ExpectedClosingBraceAfterBlock { opening, .. } => diagnostic
+                .with_primary_label("expected '}'")
+                .with_secondary_label(*opening, "unclosed '{'"),
+            ParseError::ClassInheritsFromItself { class_name, .. } => {
+                diagnostic.with_secondary_label(*class_name, "class declared here")
+            }
+            ParseError::InvalidAssignmentTarget { .. } => {
+                diagnostic.with_note("only variables and properties can be assigned to")
+            }
+            _ => diagnostic,
+        }
+    }
 }
 
 #[derive(Debug, Error)]
-pub enum ParseErrorInternal {
-    #[error("unhandled token on line {line:?}")]
-    UnhandledToken { line: usize },
+pub enum ResolveError {
+    #[error("cannot read local variable {name:?} in its own initializer")]
+    ReadLocalInOwnInitializer { span: Span, name: String },
+    #[error("a variable named {name:?} already exists in this scope")]
+    DuplicateLocal {
+        span: Span,
+        name: String,
+        previous: Span,
+    },
+    #[error("cannot return from top-level code")]
+    ReturnAtTopLevel { span: Span },
+    #[error("cannot return a value from an initializer")]
+    ReturnValueFromInitializer { span: Span },
+    #[error("cannot use 'this' outside of a class")]
+    ThisOutsideClass { span: Span },
+    #[error("cannot use 'super' outside of a class")]
+    SuperOutsideClass { span: Span },
+    #[error("cannot use 'super' in a class with no superclass")]
+    SuperWithoutSuperclass { span: Span },
+}
+
+impl ResolveError {
+    pub fn span(&self) -> Span {
+        match self {
+            ResolveError::ReadLocalInOwnInitializer { span, .. }
+            | ResolveError::DuplicateLocal { span, .. }
+            | ResolveError::ReturnAtTopLevel { span }
+            | ResolveError::ReturnValueFromInitializer { span }
+            | ResolveError::ThisOutsideClass { span }
+            | ResolveError::SuperOutsideClass { span }
+            | ResolveError::SuperWithoutSuperclass { span } => *span,
+        }
+    }
+
+    pub fn diagnostic(&self) -> Diagnostic {
+        let diagnostic = Diagnostic::error(self.to_string(), self.span());
+        match self {
+            ResolveError::DuplicateLocal { previous, .. } => {
+                diagnostic.with_secondary_label(*previous, "first declared here")
+            }
+            ResolveError::SuperWithoutSuperclass { .. } => {
+                diagnostic.with_note("a class inherits from a superclass with 'class A < B'")
+            }
+            _ => diagnostic,
+        }
+    }
 }
 
 #[derive(Debug, Error)]
 pub enum RunTimeError {
-    #[error("unexpected literal {literal:?} or operand on line {line:?}")]
-    UnexpectedUnaryToken { line: usize, literal: String },
-    #[error("unexpected literals {left:?}, {right:?} or operand on line {line:?}")]
+    #[error("cannot write output")]
+    Output {
+        span: Span,
+        #[source]
+        source: std::io::Error,
+    },
+    #[error("unexpected literal {literal:?} or operand")]
+    UnexpectedUnaryToken { span: Span, literal: String },
+    #[error("unexpected literals {left:?}, {right:?} or operand")]
     UnexpectedBinaryToken {
-        line: usize,
+        span: Span,
         left: String,
         right: String,
     },
+    #[error("undefined variable {name:?}")]
+    UndefinedVariable { span: Span, name: String },
+    #[error("cannot call {callee:?}")]
+    NotCallable { span: Span, callee: String },
+    #[error("undefined property {name:?}")]
+    UndefinedProperty { span: Span, name: String },
+    #[error("only instances have properties, cannot access {object:?}")]
+    NotAnInstance { span: Span, object: String },
+    #[error("superclass {name:?} must be a class")]
+    SuperclassNotAClass { span: Span, name: String },
+    #[error("expected {expected:?} arguments but got {found:?}")]
+    ArityMismatch {
+        span: Span,
+        expected: usize,
+        found: usize,
+    },
+    #[error("stack overflow")]
+    StackOverflow { span: Span },
+    #[error("{message}")]
+    Native { span: Span, message: String },
+    #[error("expected {expected} but found {found}")]
+    TypeMismatch {
+        span: Span,
+        expected: &'static str,
+        found: &'static str,
+    },
+}
+
+impl RunTimeError {
+    /// An error reported by a native function, which is pointed at the call that failed.
+    pub fn native(message: impl Into<String>) -> Self {
+        RunTimeError::Native {
+            span: Span::default(),
+            message: message.into(),
+        }
+    }
+
+    /// A value of type `found` given where a value of type `expected` is needed. Reported by a
+    /// native function, it is pointed at the call that failed.
+    pub fn type_mismatch(
+        expected: &'static str,
+        found: &'static str,
+    ) -> Self {
+        RunTimeError::TypeMismatch {
+            span: Span::default(),
+            expected,
+            found,
+        }
+    }
+
+    /// Points an error reported by a native function at `span`.
+    pub(crate) fn at(
+        self,
+        span: Span,
+    ) -> Self {
+        match self {
+            RunTimeError::Native { message, .. } => RunTimeError::Native { span, message },
+            RunTimeError::TypeMismatch {
+                expected, found, ..
+            } => RunTimeError::TypeMismatch {
+                span,
+                expected,
+                found,
+            },
+            error => error,
+        }
+    }
+
+    pub fn span(&self) -> Span {
+        match self {
+            RunTimeError::Output { span, .. }
+            | RunTimeError::UnexpectedUnaryToken { span, .. }
+            | RunTimeError::UnexpectedBinaryToken { span, .. }
+            | RunTimeError::UndefinedVariable { span, .. }
+            | RunTimeError::NotCallable { span, .. }
+            | RunTimeError::UndefinedProperty { span, .. }
+            | RunTimeError::NotAnInstance { span, .. }
+            | RunTimeError::SuperclassNotAClass { span, .. }
+            | RunTimeError::ArityMismatch { span, .. }
+            | RunTimeError::StackOverflow { span }
+            | RunTimeError::Native { span, .. }
+            | RunTimeError::TypeMismatch { span, .. } => *span,
+        }
+    }
+
+    pub fn diagnostic(&self) -> Diagnostic {
+        let diagnostic = Diagnostic::error(self.to_string(), self.span());
+        match self {
+            RunTimeError::Output { source, .. } => diagnostic.with_note(source.to_string()),
+            _ => diagnostic,
+        }
+    }
 }
@@ -0,0 +1,91 @@
+use std::cell::RefCell;
+use std::fmt::Debug;
+use std::fmt::Display;
+use std::fmt::Formatter;
+use std::rc::Rc;
+
+use crate::ast::FunctionDecl;
+use crate::class::Instance;
+use crate::environment::Environment;
+use crate::source::Source;
+use crate::value::Value;
+
+/// A user-defined function together with the environment it was declared in.
+pub struct Function {
+    declaration: Rc<FunctionDecl>,
+    closure: Rc<RefCell<Environment>>,
+    is_initializer: bool,
+    source: Option<Rc<Source>>,
+}
+
+impl Function {
+    pub(crate) fn new(
+        declaration: Rc<FunctionDecl>,
+        closure: Rc<RefCell<Environment>>,
+        is_initializer: bool,
+        source: Option<Rc<Source>>,
+    ) -> Self {
+        Self {
+            declaration,
+            closure,
+            is_initializer,
+            source,
+        }
+    }
+
+    /// Creates a copy of this method whose `this` refers to `instance`.
+    pub(crate) fn bind(
+        &self,
+        instance: Rc<RefCell<Instance>>,
+    ) -> Self {
+        let mut environment = Environment::with_enclosing(Rc::clone(&self.closure));
+        environment.define("this".into(), Value::Instance(instance));
+        Self::new(
+            Rc::clone(&self.declaration),
+            Rc::new(RefCell::new(environment)),
+            self.is_initializer,
+            self.source.clone(),
+        )
+    }
+
+    pub(crate) fn declaration(&self) -> &FunctionDecl {
+        &self.declaration
+    }
+
+    pub(crate) fn closure(&self) -> &Rc<RefCell<Environment>> {
+        &self.closure
+    }
+
+    /// Whether this is a class's `init` method, which always returns its instance.
+    pub(crate) fn is_initializer(&self) -> bool {
+        self.is_initializer
+    }
+
+    /// The source the function was declared in, which errors raised in its body point into.
+    pub(crate) fn source(&self) -> Option<&Rc<Source>> {
+        self.source.as_ref()
+    }
+
+    pub(crate) fn arity(&self) -> usize {
+        self.declaration.params.len()
+    }
+}
+
+impl Display for Function {
+    fn fmt(
+        &self,
+        f: &mut Formatter<'_>,
+    ) -> std::fmt::Result {
+        write!(f, "<fn {}>", self.declaration.name.identifier())
+    }
+}
+
+// The closure may contain the function itself, so only the name is printed.
+impl Debug for Function {
+    fn fmt(
+        &self,
+        f: &mut Formatter<'_>,
+    ) -> std::fmt::Result {
+        Display::fmt(self, f)
+    }
+}
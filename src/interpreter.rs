@@ -1,102 +1,1085 @@
+use std::cell::RefCell;
+use std::io;
+use std::io::Write;
 use std::ops::Add;
+use std::ops::ControlFlow;
 use std::ops::Div;
 use std::ops::Mul;
 use std::ops::Not;
 use std::ops::Sub;
+use std::rc::Rc;
 
+use crate::ast::ClassDecl;
+use crate::ast::Depth;
 use crate::ast::Expr;
-use crate::ast::Literal;
+use crate::ast::FunctionDecl;
+use crate::ast::Name;
+use crate::ast::Operator;
+use crate::ast::OperatorKind;
+use crate::ast::Stmt;
+use crate::class::Class;
+use crate::class::Instance;
+use crate::class::INITIALIZER;
+use crate::environment::Environment;
+use crate::error::Error;
 use crate::error::RunTimeError;
-use crate::token::TokenKind;
+use crate::function::Function;
+use crate::native;
+use crate::native::Arity;
+use crate::source::Source;
+use crate::span::Span;
+use crate::stack;
+use crate::value::Value;
+use crate::visitor::ExprVisitor;
+use crate::visitor::StmtVisitor;
 
-pub(crate) struct Interpreter {}
+/// How many calls can be nested before a program fails with a stack overflow. The Rust stack the
+/// interpreter recurses on grows as needed, so this only bounds the memory runaway recursion uses.
+const MAX_CALL_DEPTH: usize = 10_000;
+
+pub(crate) struct Interpreter {
+    globals: Rc<RefCell<Environment>>,
+    environment: Rc<RefCell<Environment>>,
+    output: Box<dyn Write>,
+    call_depth: usize,
+    /// The source being run, which the functions it declares keep.
+    source: Option<Rc<Source>>,
+    /// The source of the innermost function a runtime error was raised in.
+    error_source: Option<Rc<Source>>,
+}
 
 impl Interpreter {
-    pub(crate) fn interpret(exr: Expr) -> Result<String, RunTimeError> {
-        Self::interpret_inner(exr).map(|l| l.to_string())
-    }
-
-    fn interpret_inner(expr: Expr) -> Result<Literal, RunTimeError> {
-        match expr {
-            Expr::NumberLiteral(n) => Ok(Literal::Number(n)),
-            Expr::BooleanLiteral(b) => Ok(Literal::Boolean(b)),
-            Expr::StringLiteral(s) => Ok(Literal::String(s)),
-            Expr::NoneLiteral => Ok(Literal::None),
-            Expr::Grouping { expression } => Self::interpret_inner(*expression),
-            Expr::Binary {
-                left,
-                operator,
-                right,
-            } => {
-                let left = Self::interpret_inner(*left)?;
-                let right = Self::interpret_inner(*right)?;
-                let op = operator.kind();
-                match (op, left, right) {
-                    (TokenKind::Minus { .. }, Literal::Number(l), Literal::Number(r)) => {
-                        Ok(Literal::Number(l.sub(r)))
-                    }
-                    (TokenKind::Slash { .. }, Literal::Number(l), Literal::Number(r)) => {
-                        Ok(Literal::Number(l.div(r)))
-                    }
-                    (TokenKind::Star { .. }, Literal::Number(l), Literal::Number(r)) => {
-                        Ok(Literal::Number(l.mul(r)))
-                    }
-                    (TokenKind::Plus { .. }, Literal::Number(l), Literal::Number(r)) => {
-                        Ok(Literal::Number(l.add(r)))
-                    }
-                    (TokenKind::Greater { .. }, Literal::Number(l), Literal::Number(r)) => {
-                        Ok(Literal::Boolean(l > r))
-                    }
-                    (TokenKind::GreaterEqual { .. }, Literal::Number(l), Literal::Number(r)) => {
-                        Ok(Literal::Boolean(l >= r))
-                    }
-                    (TokenKind::Less { .. }, Literal::Number(l), Literal::Number(r)) => {
-                        Ok(Literal::Boolean(l < r))
-                    }
-                    (TokenKind::LessEqual { .. }, Literal::Number(l), Literal::Number(r)) => {
-                        Ok(Literal::Boolean(l <= r))
-                    }
-
-                    (TokenKind::BangEqual { .. }, Literal::Number(l), Literal::Number(r)) => {
-                        Ok(Literal::Boolean(l != r))
-                    }
-                    (TokenKind::BangEqual { .. }, Literal::String(l), Literal::String(r)) => {
-                        Ok(Literal::Boolean(l != r))
-                    }
-                    (TokenKind::BangEqual { .. }, Literal::None, Literal::None) => {
-                        Ok(Literal::Boolean(false))
-                    }
-                    (TokenKind::BangEqual { .. }, Literal::None, _) => Ok(Literal::Boolean(true)),
-                    (TokenKind::EqualEqual { .. }, Literal::Number(l), Literal::Number(r)) => {
-                        Ok(Literal::Boolean(l == r))
-                    }
-                    (TokenKind::EqualEqual { .. }, Literal::String(l), Literal::String(r)) => {
-                        Ok(Literal::Boolean(l == r))
-                    }
-                    (TokenKind::EqualEqual { .. }, Literal::None, Literal::None) => {
-                        Ok(Literal::Boolean(true))
-                    }
-                    (TokenKind::EqualEqual { .. }, Literal::None, _) => Ok(Literal::Boolean(false)),
-                    (_, l, r) => Err(RunTimeError::UnexpectedBinaryToken {
-                        line: operator.line(),
-                        left: l.to_string(),
-                        right: r.to_string(),
-                    }),
+    pub(crate) fn new() -> Self {
+        Self::with_output(io::stdout())
+    }
+
+    /// Creates an interpreter that writes the output of `print` statements to `output`.
+    pub(crate) fn with_output(output: impl Write + 'static) -> Self {
+        let mut globals = Environment::new();
+        globals.define(
+            "clock".into(),
+            Value::NativeFunction(Rc::new(native::clock())),
+        );
+        let globals = Rc::new(RefCell::new(globals));
+        Self {
+            environment: Rc::clone(&globals),
+            globals,
+            output: Box::new(output),
+            call_depth: 0,
+            source: None,
+            error_source: None,
+        }
+    }
+
+    /// The value of the global variable `name`, if it is defined.
+    pub(crate) fn get_global(
+        &self,
+        name: &str,
+    ) -> Option<Value> {
+        self.globals.borrow().get_own(name).cloned()
+    }
+
+    /// Defines the global variable `name`, replacing any previous definition.
+    pub(crate) fn define_global(
+        &mut self,
+        name: &str,
+        value: Value,
+    ) {
+        self.globals.borrow_mut().define(name.into(), value);
+    }
+
+    /// Removes every global variable. Functions and classes refer to the globals they were
+    /// declared in, so the globals are only freed once this breaks those cycles.
+    pub(crate) fn clear_globals(&mut self) {
+        self.globals.borrow_mut().clear();
+    }
+
+    /// Runs `statements`, parsed from `source`. Runtime errors are reported against the source
+    /// they were raised in, which is an earlier one for errors in functions it declared.
+    pub(crate) fn interpret_source(
+        &mut self,
+        statements: &[Stmt],
+        source: &Rc<Source>,
+    ) -> Result<(), Error> {
+        self.source = Some(Rc::clone(source));
+        self.error_source = None;
+        let result = self.interpret(statements);
+        self.source = None;
+        result.map_err(|error| {
+            let origin = self
+                .error_source
+                .take()
+                .unwrap_or_else(|| Rc::clone(source));
+            Error::from(error).in_source(origin.name.as_str(), origin.code.as_str())
+        })
+    }
+
+    pub(crate) fn interpret(
+        &mut self,
+        statements: &[Stmt],
+    ) -> Result<(), RunTimeError> {
+        for statement in statements {
+            if self.execute(statement)?.is_break() {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    /// Executes a single statement. A `return` statement breaks out of the surrounding function
+    /// with its value.
+    fn execute(
+        &mut self,
+        statement: &Stmt,
+    ) -> Result<ControlFlow<Value>, RunTimeError> {
+        stack::grow(|| statement.accept(self))
+    }
+
+    fn evaluate(
+        &mut self,
+        expr: &Expr,
+    ) -> Result<Value, RunTimeError> {
+        stack::grow(|| expr.accept(self))
+    }
+
+    /// Executes `statements` in `environment`, restoring the current environment afterwards even
+    /// if one of them fails or returns.
+    fn execute_block(
+        &mut self,
+        statements: &[Stmt],
+        environment: Environment,
+    ) -> Result<ControlFlow<Value>, RunTimeError> {
+        let previous = std::mem::replace(&mut self.environment, Rc::new(RefCell::new(environment)));
+        let mut result = Ok(ControlFlow::Continue(()));
+        for statement in statements {
+            result = self.execute(statement);
+            if !matches!(result, Ok(ControlFlow::Continue(()))) {
+                break;
+            }
+        }
+        self.environment = previous;
+        result
+    }
+
+    fn call(
+        &mut self,
+        callee: Value,
+        span: Span,
+        arguments: Vec<Value>,
+    ) -> Result<Value, RunTimeError> {
+        if self.call_depth == MAX_CALL_DEPTH {
+            return Err(RunTimeError::StackOverflow { span });
+        }
+        self.call_depth += 1;
+        let result = self.call_value(callee, span, arguments);
+        self.call_depth -= 1;
+        result
+    }
+
+    fn call_value(
+        &mut self,
+        callee: Value,
+        span: Span,
+        arguments: Vec<Value>,
+    ) -> Result<Value, RunTimeError> {
+        match callee {
+            Value::Function(function) => {
+                check_arity(function.arity(), &arguments, span)?;
+                self.call_function(&function, arguments)
+            }
+            Value::NativeFunction(function) => {
+                if let Arity::Fixed(arity) = function.arity() {
+                    check_arity(arity, &arguments, span)?;
                 }
+                function.call(&arguments, span)
+            }
+            Value::Class(class) => {
+                check_arity(class.arity(), &arguments, span)?;
+                let instance = Rc::new(RefCell::new(Instance::new(Rc::clone(&class))));
+                if let Some(initializer) = class.find_method(INITIALIZER) {
+                    self.call_function(&initializer.bind(Rc::clone(&instance)), arguments)?;
+                }
+                Ok(Value::Instance(instance))
+            }
+            callee => Err(RunTimeError::NotCallable {
+                span,
+                callee: callee.to_string(),
+            }),
+        }
+    }
+
+    fn call_function(
+        &mut self,
+        function: &Function,
+        arguments: Vec<Value>,
+    ) -> Result<Value, RunTimeError> {
+        let mut environment = Environment::with_enclosing(Rc::clone(function.closure()));
+        let declaration = function.declaration();
+        for (param, argument) in declaration.params.iter().zip(arguments) {
+            environment.define(param.shared(), argument);
+        }
+        let returned = match self.execute_block(&declaration.body, environment) {
+            Ok(returned) => returned,
+            Err(error) => {
+                if self.error_source.is_none() {
+                    self.error_source = function.source().cloned();
+                }
+                return Err(error);
+            }
+        };
+        if function.is_initializer() {
+            return Ok(function.closure().borrow().get_at(0, "this"));
+        }
+        match returned {
+            ControlFlow::Break(value) => Ok(value),
+            ControlFlow::Continue(()) => Ok(Value::None),
+        }
+    }
+
+    fn look_up_variable(
+        &self,
+        name: &Name,
+        depth: &Depth,
+    ) -> Result<Value, RunTimeError> {
+        match depth.get() {
+            Some(distance) => Ok(self
+                .environment
+                .borrow()
+                .get_at(distance, name.identifier())),
+            None => self.globals.borrow().get(name),
+        }
+    }
+}
+
+impl StmtVisitor for Interpreter {
+    type Output = Result<ControlFlow<Value>, RunTimeError>;
+
+    fn visit_expression_statement(
+        &mut self,
+        expression: &Expr,
+    ) -> Self::Output {
+        self.evaluate(expression)?;
+        Ok(ControlFlow::Continue(()))
+    }
+
+    fn visit_print(
+        &mut self,
+        expression: &Expr,
+        span: Span,
+    ) -> Self::Output {
+        let value = self.evaluate(expression)?;
+        writeln!(self.output, "{value}").map_err(|source| RunTimeError::Output { span, source })?;
+        Ok(ControlFlow::Continue(()))
+    }
+
+    fn visit_var(
+        &mut self,
+        name: &Name,
+        initializer: Option<&Expr>,
+    ) -> Self::Output {
+        let value = match initializer {
+            Some(initializer) => self.evaluate(initializer)?,
+            None => Value::None,
+        };
+        self.environment.borrow_mut().define(name.shared(), value);
+        Ok(ControlFlow::Continue(()))
+    }
+
+    fn visit_block(
+        &mut self,
+        statements: &[Stmt],
+    ) -> Self::Output {
+        let environment = Environment::with_enclosing(Rc::clone(&self.environment));
+        self.execute_block(statements, environment)
+    }
+
+    fn visit_if(
+        &mut self,
+        condition: &Expr,
+        then_branch: &Stmt,
+        else_branch: Option<&Stmt>,
+    ) -> Self::Output {
+        if self.evaluate(condition)?.is_truthy() {
+            self.execute(then_branch)
+        } else if let Some(else_branch) = else_branch {
+            self.execute(else_branch)
+        } else {
+            Ok(ControlFlow::Continue(()))
+        }
+    }
+
+    fn visit_while(
+        &mut self,
+        condition: &Expr,
+        body: &Stmt,
+    ) -> Self::Output {
+        while self.evaluate(condition)?.is_truthy() {
+            if let returned @ ControlFlow::Break(_) = self.execute(body)? {
+                return Ok(returned);
             }
-            Expr::Unary { operator, right } => {
-                let right = Self::interpret_inner(*right)?;
-                let op = operator.kind();
-                match (op, right) {
-                    (TokenKind::Minus { .. }, Literal::Number(n)) => Ok(Literal::Number(-n)),
-                    (TokenKind::Bang { .. }, Literal::Boolean(n)) => Ok(Literal::Boolean(n.not())),
-                    (TokenKind::Bang { .. }, Literal::None) => Ok(Literal::Boolean(true)),
-                    (_, r) => Err(RunTimeError::UnexpectedUnaryToken {
-                        line: operator.line(),
-                        literal: r.to_string(),
-                    }),
+        }
+        Ok(ControlFlow::Continue(()))
+    }
+
+    fn visit_function(
+        &mut self,
+        declaration: &Rc<FunctionDecl>,
+    ) -> Self::Output {
+        let function = Function::new(
+            Rc::clone(declaration),
+            Rc::clone(&self.environment),
+            false,
+            self.source.clone(),
+        );
+        self.environment.borrow_mut().define(
+            declaration.name.shared(),
+            Value::Function(Rc::new(function)),
+        );
+        Ok(ControlFlow::Continue(()))
+    }
+
+    fn visit_class(
+        &mut self,
+        declaration: &ClassDecl,
+    ) -> Self::Output {
+        let superclass = match &declaration.superclass {
+            Some(superclass) => match self.evaluate(superclass)? {
+                Value::Class(class) => Some(class),
+                _ => {
+                    let Expr::Variable { name, .. } = superclass else {
+                        unreachable!("the parser only accepts names as superclasses")
+                    };
+                    return Err(RunTimeError::SuperclassNotAClass {
+                        span: name.span(),
+                        name: name.identifier().to_string(),
+                    });
                 }
+            },
+            None => None,
+        };
+
+        // Methods of a subclass close over an extra scope that binds `super`.
+        let mut method_closure = Rc::clone(&self.environment);
+        if let Some(superclass) = &superclass {
+            let mut environment = Environment::with_enclosing(method_closure);
+            environment.define("super".into(), Value::Class(Rc::clone(superclass)));
+            method_closure = Rc::new(RefCell::new(environment));
+        }
+
+        let methods = declaration
+            .methods
+            .iter()
+            .map(|method| {
+                let function = Function::new(
+                    Rc::clone(method),
+                    Rc::clone(&method_closure),
+                    method.name.identifier() == INITIALIZER,
+                    self.source.clone(),
+                );
+                (method.name.shared(), Rc::new(function))
+            })
+            .collect();
+        let name = declaration.name.shared();
+        let class = Class::new(Rc::clone(&name), superclass, methods);
+        self.environment
+            .borrow_mut()
+            .define(name, Value::Class(Rc::new(class)));
+        Ok(ControlFlow::Continue(()))
+    }
+
+    fn visit_return(
+        &mut self,
+        _keyword: Span,
+        value: Option<&Expr>,
+    ) -> Self::Output {
+        let value = match value {
+            Some(value) => self.evaluate(value)?,
+            None => Value::None,
+        };
+        Ok(ControlFlow::Break(value))
+    }
+}
+
+impl ExprVisitor for Interpreter {
+    type Output = Result<Value, RunTimeError>;
+
+    fn visit_binary(
+        &mut self,
+        left: &Expr,
+        operator: &Operator,
+        right: &Expr,
+    ) -> Self::Output {
+        let left = self.evaluate(left)?;
+        let right = self.evaluate(right)?;
+        let op = operator.kind();
+        match (op, left, right) {
+            (OperatorKind::Minus, Value::Number(l), Value::Number(r)) => {
+                Ok(Value::Number(l.sub(r)))
             }
+            (OperatorKind::Slash, Value::Number(l), Value::Number(r)) => {
+                Ok(Value::Number(l.div(r)))
+            }
+            (OperatorKind::Star, Value::Number(l), Value::Number(r)) => Ok(Value::Number(l.mul(r))),
+            (OperatorKind::Plus, Value::Number(l), Value::Number(r)) => Ok(Value::Number(l.add(r))),
+            (OperatorKind::Plus, Value::String(l), Value::String(r)) => {
+                Ok(Value::String(format!("{l}{r}").into()))
+            }
+            (OperatorKind::Greater, Value::Number(l), Value::Number(r)) => {
+                Ok(Value::Boolean(l > r))
+            }
+            (OperatorKind::GreaterEqual, Value::Number(l), Value::Number(r)) => {
+                Ok(Value::Boolean(l >= r))
+            }
+            (OperatorKind::Less, Value::Number(l), Value::Number(r)) => Ok(Value::Boolean(l < r)),
+            (OperatorKind::LessEqual, Value::Number(l), Value::Number(r)) => {
+                Ok(Value::Boolean(l <= r))
+            }
+            (OperatorKind::EqualEqual, l, r) => Ok(Value::Boolean(l == r)),
+            (OperatorKind::BangEqual, l, r) => Ok(Value::Boolean(l != r)),
+            (_, l, r) => Err(RunTimeError::UnexpectedBinaryToken {
+                span: operator.span(),
+                left: l.to_string(),
+                right: r.to_string(),
+            }),
+        }
+    }
+
+    fn visit_unary(
+        &mut self,
+        operator: &Operator,
+        right: &Expr,
+    ) -> Self::Output {
+        let right = self.evaluate(right)?;
+        let op = operator.kind();
+        match (op, right) {
+            (OperatorKind::Minus, Value::Number(n)) => Ok(Value::Number(-n)),
+            (OperatorKind::Bang, r) => Ok(Value::Boolean(r.is_truthy().not())),
+            (_, r) => Err(RunTimeError::UnexpectedUnaryToken {
+                span: operator.span(),
+                literal: r.to_string(),
+            }),
+        }
+    }
+
+    fn visit_logical(
+        &mut self,
+        left: &Expr,
+        operator: &Operator,
+        right: &Expr,
+    ) -> Self::Output {
+        let left = self.evaluate(left)?;
+        let short_circuits = match operator.kind() {
+            OperatorKind::Or => left.is_truthy(),
+            _ => !left.is_truthy(),
+        };
+        if short_circuits {
+            Ok(left)
+        } else {
+            self.evaluate(right)
+        }
+    }
+
+    fn visit_grouping(
+        &mut self,
+        expression: &Expr,
+    ) -> Self::Output {
+        self.evaluate(expression)
+    }
+
+    fn visit_variable(
+        &mut self,
+        name: &Name,
+        depth: &Depth,
+    ) -> Self::Output {
+        self.look_up_variable(name, depth)
+    }
+
+    fn visit_assign(
+        &mut self,
+        name: &Name,
+        value: &Expr,
+        depth: &Depth,
+    ) -> Self::Output {
+        let value = self.evaluate(value)?;
+        match depth.get() {
+            Some(distance) => {
+                self.environment
+                    .borrow_mut()
+                    .assign_at(distance, name.identifier(), value.clone())
+            }
+            None => self.globals.borrow_mut().assign(name, value.clone())?,
+        }
+        Ok(value)
+    }
+
+    fn visit_get(
+        &mut self,
+        object: &Expr,
+        name: &Name,
+    ) -> Self::Output {
+        match self.evaluate(object)? {
+            Value::Instance(instance) => Instance::get(&instance, name),
+            object => Err(RunTimeError::NotAnInstance {
+                span: name.span(),
+                object: object.to_string(),
+            }),
+        }
+    }
+
+    fn visit_set(
+        &mut self,
+        object: &Expr,
+        name: &Name,
+        value: &Expr,
+    ) -> Self::Output {
+        let instance = match self.evaluate(object)? {
+            Value::Instance(instance) => instance,
+            object => {
+                return Err(RunTimeError::NotAnInstance {
+                    span: name.span(),
+                    object: object.to_string(),
+                })
+            }
+        };
+        let value = self.evaluate(value)?;
+        instance.borrow_mut().set(name, value.clone());
+        Ok(value)
+    }
+
+    fn visit_this(
+        &mut self,
+        keyword: &Name,
+        depth: &Depth,
+    ) -> Self::Output {
+        self.look_up_variable(keyword, depth)
+    }
+
+    fn visit_super(
+        &mut self,
+        keyword: &Name,
+        method: &Name,
+        depth: &Depth,
+    ) -> Self::Output {
+        let distance = depth
+            .get()
+            .expect("`super` is always resolved to a local scope");
+        let environment = self.environment.borrow();
+        let Value::Class(superclass) = environment.get_at(distance, keyword.identifier()) else {
+            unreachable!("`super` is only ever bound to a class")
+        };
+        // `this` is bound in the scope right inside the one binding `super`.
+        let Value::Instance(instance) = environment.get_at(distance - 1, "this") else {
+            unreachable!("`this` is only ever bound to an instance")
+        };
+        match superclass.find_method(method.identifier()) {
+            Some(found) => Ok(Value::Function(Rc::new(found.bind(instance)))),
+            None => Err(RunTimeError::UndefinedProperty {
+                span: method.span(),
+                name: method.identifier().to_string(),
+            }),
+        }
+    }
+
+    fn visit_call(
+        &mut self,
+        callee: &Expr,
+        paren: Span,
+        arguments: &[Expr],
+    ) -> Self::Output {
+        let span = callee.span().merge(paren);
+        let callee = self.evaluate(callee)?;
+        let arguments = arguments
+            .iter()
+            .map(|argument| self.evaluate(argument))
+            .collect::<Result<Vec<_>, _>>()?;
+        self.call(callee, span, arguments)
+    }
+
+    fn visit_string_literal(
+        &mut self,
+        value: &Rc<str>,
+    ) -> Self::Output {
+        Ok(Value::String(Rc::clone(value)))
+    }
+
+    fn visit_boolean_literal(
+        &mut self,
+        value: bool,
+    ) -> Self::Output {
+        Ok(Value::Boolean(value))
+    }
+
+    fn visit_none_literal(&mut self) -> Self::Output {
+        Ok(Value::None)
+    }
+
+    fn visit_number_literal(
+        &mut self,
+        value: f64,
+    ) -> Self::Output {
+        Ok(Value::Number(value))
+    }
+}
+
+fn check_arity(
+    expected: usize,
+    arguments: &[Value],
+    span: Span,
+) -> Result<(), RunTimeError> {
+    if arguments.len() != expected {
+        return Err(RunTimeError::ArityMismatch {
+            span,
+            expected,
+            found: arguments.len(),
+        });
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+    use std::collections::HashMap;
+    use std::io::Write;
+    use std::rc::Rc;
+
+    use crate::convert::IntoLox;
+    use crate::error::RunTimeError;
+    use crate::interpreter::Interpreter;
+    use crate::lexer::Lexer;
+    use crate::parser::Parser;
+    use crate::resolver::Resolver;
+    use crate::span::Span;
+
+    /// An output sink that can still be read after the interpreter took ownership of it.
+    #[derive(Clone, Default)]
+    struct SharedBuffer(Rc<RefCell<Vec<u8>>>);
+
+    impl Write for SharedBuffer {
+        fn write(
+            &mut self,
+            buf: &[u8],
+        ) -> std::io::Result<usize> {
+            self.0.borrow_mut().write(buf)
         }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    fn run(source: &str) -> Result<String, RunTimeError> {
+        let statements = Parser::new(Lexer::new(source).flatten()).parse().unwrap();
+        Resolver::resolve(&statements).unwrap();
+        let output = SharedBuffer::default();
+        Interpreter::with_output(output.clone()).interpret(&statements)?;
+        let printed = String::from_utf8(output.0.take()).unwrap();
+        Ok(printed)
+    }
+
+    #[test]
+    fn test_print_statements_are_executed_in_order() {
+        let output = run("print 1 + 2;\n4 * 5;\nprint 3 > 2;").unwrap();
+        assert_eq!(output, "3\ntrue\n");
+    }
+
+    #[test]
+    fn test_statements_after_runtime_error_are_not_executed() {
+        let result = run("print 1;\nprint -nil;\nprint 2;");
+        assert!(matches!(
+            result,
+            Err(RunTimeError::UnexpectedUnaryToken {
+                span: Span { line: 2, .. },
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn test_variables_can_be_defined_read_and_assigned() {
+        let output =
+            run("var a = 1;\nvar b;\nprint b;\nb = a = a + 1;\nprint a;\nprint b;").unwrap();
+        assert_eq!(output, "Nil\n2\n2\n");
+    }
+
+    #[test]
+    fn test_redeclaring_a_global_variable_replaces_it() {
+        let output = run("var a = 1;\nvar a = \"one\";\nprint a;").unwrap();
+        assert_eq!(output, "one\n");
+    }
+
+    #[test]
+    fn test_reading_undefined_variable_fails() {
+        let result = run("var a = 1;\nprint b;");
+        assert!(matches!(
+            result,
+            Err(RunTimeError::UndefinedVariable { span: Span { line: 2, .. }, name }) if name == "b"
+        ));
+    }
+
+    #[test]
+    fn test_runtime_errors_point_at_the_offending_code() {
+        let result = run("var a = 1;\nprint a + b;");
+        assert!(matches!(
+            result,
+            Err(RunTimeError::UndefinedVariable { span, .. }) if span == Span::new(21, 22, 2, 11)
+        ));
+        let result = run("fun f(a) {}\nf(1, 2);");
+        assert!(matches!(
+            result,
+            Err(RunTimeError::ArityMismatch { span, .. }) if span == Span::new(12, 19, 2, 1)
+        ));
+    }
+
+    #[test]
+    fn test_plus_concatenates_strings() {
+        let output = run(
+            "var s = \"\";\nfor (var i = 0; i < 3; i = i + 1) s = s + \"ab\";\nprint s + \"!\";\nprint \"a\" + \"b\" == \"ab\";",
+        )
+        .unwrap();
+        assert_eq!(output, "ababab!\ntrue\n");
+        let result = run("print \"a\" + 1;");
+        assert!(matches!(
+            result,
+            Err(RunTimeError::UnexpectedBinaryToken { left, right, .. }) if left == "a" && right == "1"
+        ));
+    }
+
+    #[test]
+    fn test_assigning_undefined_variable_fails() {
+        let result = run("c = 1;");
+        assert!(matches!(
+            result,
+            Err(RunTimeError::UndefinedVariable { span: Span { line: 1, .. }, name }) if name == "c"
+        ));
+    }
+
+    #[test]
+    fn test_nil_equals_only_nil_on_either_side() {
+        let output =
+            run("print nil == nil;\nprint nil == 1;\nprint \"x\" == nil;\nprint false != nil;")
+                .unwrap();
+        assert_eq!(output, "true\nfalse\nfalse\ntrue\n");
+    }
+
+    #[test]
+    fn test_booleans_compare_by_value() {
+        let output =
+            run("print true == true;\nprint true == false;\nprint false != true;").unwrap();
+        assert_eq!(output, "true\nfalse\ntrue\n");
+    }
+
+    #[test]
+    fn test_values_of_different_types_are_not_equal() {
+        let output = run("print 1 == \"1\";\nprint true == 1;\nprint \"\" != false;").unwrap();
+        assert_eq!(output, "false\nfalse\ntrue\n");
+    }
+
+    #[test]
+    fn test_functions_classes_and_instances_compare_by_identity() {
+        let output = run(
+            "fun f() {}\nfun g() {}\nclass A {}\nvar a = A();\n\
+             print f == f;\nprint f == g;\nprint A == A;\nprint a == a;\nprint a == A();\nprint clock == clock;",
+        )
+        .unwrap();
+        assert_eq!(output, "true\nfalse\ntrue\ntrue\nfalse\ntrue\n");
+    }
+
+    #[test]
+    fn test_loops_can_compare_instances_with_nil() {
+        let output = run(
+            "class Node { init(value, next) { this.value = value; this.next = next; } }\n\
+             var n = Node(1, Node(2, nil));\nwhile (n != nil) { print n.value; n = n.next; }",
+        )
+        .unwrap();
+        assert_eq!(output, "1\n2\n");
+    }
+
+    #[test]
+    fn test_lists_and_maps_compare_by_contents_and_print() {
+        let statements = Parser::new(
+            Lexer::new("print xs;\nprint m;\nprint xs == ys;\nprint xs == m;\nprint m != m;")
+                .flatten(),
+        )
+        .parse()
+        .unwrap();
+        Resolver::resolve(&statements).unwrap();
+        let output = SharedBuffer::default();
+        let mut interpreter = Interpreter::with_output(output.clone());
+        interpreter.define_global("xs", vec![1.0, 2.0].into_lox());
+        interpreter.define_global("ys", vec![1.0, 2.0].into_lox());
+        interpreter.define_global("m", HashMap::from([("a".to_string(), "b")]).into_lox());
+        interpreter.interpret(&statements).unwrap();
+
+        let printed = String::from_utf8(output.0.take()).unwrap();
+        assert_eq!(printed, "[1, 2]\n{a: b}\ntrue\nfalse\nfalse\n");
+    }
+
+    #[test]
+    fn test_blocks_shadow_and_drop_inner_variables() {
+        let output = run(
+            "var a = \"global a\";\nvar b = \"global b\";\n{\n  var a = \"inner a\";\n  b = \"assigned b\";\n  print a;\n  print b;\n}\nprint a;\nprint b;",
+        )
+        .unwrap();
+        assert_eq!(output, "inner a\nassigned b\nglobal a\nassigned b\n");
+    }
+
+    #[test]
+    fn test_block_variables_do_not_leak() {
+        let result = run("{\n  var temporary = 1;\n}\nprint temporary;");
+        assert!(matches!(
+            result,
+            Err(RunTimeError::UndefinedVariable { span: Span { line: 4, .. }, name }) if name == "temporary"
+        ));
+    }
+
+    #[test]
+    fn test_bang_negates_truthiness_of_any_value() {
+        let output =
+            run("print !nil;\nprint !false;\nprint !0;\nprint !\"\";\nprint !!true;").unwrap();
+        assert_eq!(output, "true\ntrue\nfalse\nfalse\ntrue\n");
+    }
+
+    #[test]
+    fn test_if_else_branches_on_truthiness() {
+        let output =
+            run("if (nil) print 1; else print 2;\nif (0) print 3;\nif (false) print 4;").unwrap();
+        assert_eq!(output, "2\n3\n");
+    }
+
+    #[test]
+    fn test_logical_operators_short_circuit_and_return_operands() {
+        let output = run(
+            "print nil or \"yes\";\nprint 1 or undefined;\nprint false and undefined;\nprint 1 and 2;",
+        )
+        .unwrap();
+        assert_eq!(output, "yes\n1\nfalse\n2\n");
+    }
+
+    #[test]
+    fn test_while_and_for_loops() {
+        let output = run(
+            "var i = 0;\nwhile (i < 2) { print i; i = i + 1; }\nfor (var j = 0; j < 3; j = j + 1) print j * 10;",
+        )
+        .unwrap();
+        assert_eq!(output, "0\n1\n0\n10\n20\n");
+    }
+
+    #[test]
+    fn test_conditions_can_compare_booleans() {
+        let output = run(
+            "var done = false;\nvar i = 0;\nwhile (done == false) { i = i + 1; if (i == 3) done = true; }\n\
+             print i;\nif (true == true) print \"yes\";\nif (\"x\" == nil) print \"no\"; else print \"nil\";",
+        )
+        .unwrap();
+        assert_eq!(output, "3\nyes\nnil\n");
+    }
+
+    #[test]
+    fn test_for_loop_variable_is_scoped_to_the_loop() {
+        let result = run("for (var k = 0; k < 1; k = k + 1) {}\nprint k;");
+        assert!(matches!(
+            result,
+            Err(RunTimeError::UndefinedVariable { span: Span { line: 2, .. }, name }) if name == "k"
+        ));
+    }
+
+    #[test]
+    fn test_functions_can_be_declared_called_and_return_values() {
+        let output = run(
+            "fun add(a, b) { return a + b; }\nfun nothing() {}\nprint add(1, 2);\nprint nothing();\nprint add;",
+        )
+        .unwrap();
+        assert_eq!(output, "3\nNil\n<fn add>\n");
+    }
+
+    #[test]
+    fn test_return_exits_nested_loops_and_blocks() {
+        let output = run(
+            "fun find() {\n  for (var i = 0; i < 10; i = i + 1) {\n    if (i > 2) { return i; }\n  }\n}\nprint find();",
+        )
+        .unwrap();
+        assert_eq!(output, "3\n");
+    }
+
+    #[test]
+    fn test_recursive_functions() {
+        let output = run(
+            "fun fib(n) { if (n < 2) return n; return fib(n - 1) + fib(n - 2); }\nprint fib(10);",
+        )
+        .unwrap();
+        assert_eq!(output, "55\n");
+    }
+
+    #[test]
+    fn test_unbounded_recursion_is_a_stack_overflow() {
+        let result = run("fun f(n) { return f(n + 1); }\nf(0);");
+        assert!(matches!(
+            result,
+            Err(RunTimeError::StackOverflow {
+                span: Span {
+                    line: 1,
+                    column: 19,
+                    ..
+                }
+            })
+        ));
+    }
+
+    #[test]
+    fn test_recursion_below_the_limit_succeeds() {
+        let output = run(
+            "fun count(n) { if (n == 0) return 0; return 1 + count(n - 1); }\nprint count(5000);",
+        )
+        .unwrap();
+        assert_eq!(output, "5000\n");
+    }
+
+    #[test]
+    fn test_closures_capture_their_defining_environment() {
+        let output = run(
+            "fun makeCounter() {\n  var i = 0;\n  fun count() { i = i + 1; return i; }\n  return count;\n}\nvar counter = makeCounter();\nprint counter();\nprint counter();",
+        )
+        .unwrap();
+        assert_eq!(output, "1\n2\n");
+    }
+
+    #[test]
+    fn test_calling_with_wrong_number_of_arguments_fails() {
+        let result = run("fun f(a) {}\nf(1, 2);");
+        assert!(matches!(
+            result,
+            Err(RunTimeError::ArityMismatch {
+                span: Span { line: 2, .. },
+                expected: 1,
+                found: 2
+            })
+        ));
+    }
+
+    #[test]
+    fn test_calling_a_non_callable_fails() {
+        let result = run("\"not a function\"();");
+        assert!(matches!(
+            result,
+            Err(RunTimeError::NotCallable {
+                span: Span { line: 1, .. },
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn test_classes_create_instances_with_fields() {
+        let output = run(
+            "class Point {}\nvar p = Point();\np.x = 1;\np.y = p.x + 1;\nprint p.y;\nprint Point;\nprint p;",
+        )
+        .unwrap();
+        assert_eq!(output, "2\nPoint\nPoint instance\n");
+    }
+
+    #[test]
+    fn test_methods_are_bound_to_their_instance() {
+        let output = run(
+            "class Greeter {\n  greet() { return this.name; }\n}\nvar g = Greeter();\ng.name = \"g\";\nvar greet = g.greet;\ng.name = \"renamed\";\nprint greet();",
+        )
+        .unwrap();
+        assert_eq!(output, "renamed\n");
+    }
+
+    #[test]
+    fn test_initializers_set_up_and_return_the_instance() {
+        let output = run(
+            "class Counter {\n  init(start) { this.count = start; return; }\n  increment() { this.count = this.count + 1; return this; }\n}\nvar c = Counter(5);\nprint c.increment().increment().count;\nprint c.init(0).count;",
+        )
+        .unwrap();
+        assert_eq!(output, "7\n0\n");
+    }
+
+    #[test]
+    fn test_calling_class_with_wrong_number_of_arguments_fails() {
+        let result = run("class A { init(a) {} }\nA();");
+        assert!(matches!(
+            result,
+            Err(RunTimeError::ArityMismatch {
+                span: Span { line: 2, .. },
+                expected: 1,
+                found: 0
+            })
+        ));
+    }
+
+    #[test]
+    fn test_reading_undefined_property_fails() {
+        let result = run("class A {}\nA().missing;");
+        assert!(matches!(
+            result,
+            Err(RunTimeError::UndefinedProperty { span: Span { line: 2, .. }, name }) if name == "missing"
+        ));
+    }
+
+    #[test]
+    fn test_accessing_properties_of_non_instances_fails() {
+        let get = run("var a = 1;\nprint a.b;");
+        assert!(matches!(
+            get,
+            Err(RunTimeError::NotAnInstance {
+                span: Span { line: 2, .. },
+                ..
+            })
+        ));
+        let set = run("\"string\".field = 1;");
+        assert!(matches!(
+            set,
+            Err(RunTimeError::NotAnInstance {
+                span: Span { line: 1, .. },
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn test_subclasses_inherit_methods() {
+        let output = run(
+            "class A { method() { return \"A method\"; } }\nclass B < A {}\nclass C < B {}\nprint C().method();",
+        )
+        .unwrap();
+        assert_eq!(output, "A method\n");
+    }
+
+    #[test]
+    fn test_super_calls_are_bound_to_the_current_instance() {
+        let output = run(
+            "class A {\n  init(name) { this.name = name; }\n  describe() { return this.name; }\n}\nclass B < A {\n  init(name) { super.init(name); }\n  describe() { return super.describe; }\n}\nclass C < B {}\nprint C(\"c\").describe()();",
+        )
+        .unwrap();
+        assert_eq!(output, "c\n");
+    }
+
+    #[test]
+    fn test_inheriting_from_non_class_fails() {
+        let result = run("var NotAClass = 1;\nclass A < NotAClass {}");
+        assert!(matches!(
+            result,
+            Err(RunTimeError::SuperclassNotAClass { span: Span { line: 2, .. }, name }) if name == "NotAClass"
+        ));
+    }
+
+    #[test]
+    fn test_calling_undefined_super_method_fails() {
+        let result = run("class A {}\nclass B < A { m() { super.m(); } }\nB().m();");
+        assert!(matches!(
+            result,
+            Err(RunTimeError::UndefinedProperty { span: Span { line: 2, .. }, name }) if name == "m"
+        ));
+    }
+
+    #[test]
+    fn test_closures_bind_variables_statically() {
+        let output = run(
+            "var a = \"global\";\n{\n  fun showA() { print a; }\n  showA();\n  var a = \"block\";\n  showA();\n}",
+        )
+        .unwrap();
+        assert_eq!(output, "global\nglobal\n");
     }
 }
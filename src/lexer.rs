@@ -1,13 +1,20 @@
+use std::borrow::Cow;
 use std::collections::HashMap;
+use std::iter::Peekable;
+use std::ops::Range;
+use std::str::CharIndices;
+use std::str::Chars;
 
 use itertools::peek_nth;
+use itertools::Itertools;
 use itertools::PeekNth;
 use once_cell::sync::Lazy;
 
 use crate::error::LexicalError;
+use crate::span::Span;
+use crate::token::LexResult;
 use crate::token::Token;
 use crate::token::TokenKind;
-use crate::token::Tokens;
 
 static RESERVED_KEYWORDS: Lazy<HashMap<&str, TokenKind>> = Lazy::new(|| {
     HashMap::from_iter([
@@ -30,144 +37,219 @@ static RESERVED_KEYWORDS: Lazy<HashMap<&str, TokenKind>> = Lazy::new(|| {
     ])
 });
 
+/// Lazily splits a source into tokens, ending with an `Eof` token. Lexical errors are yielded in
+/// place of the invalid tokens, after which lexing carries on with the rest of the source.
 #[derive(Debug)]
-pub(crate) struct Lexer;
+pub(crate) struct Lexer<'a> {
+    source: &'a str,
+    // TODO: Iterates over Unicode Scalar Values instead of grapheme clusters.
+    characters: IndexedPeekNth<Chars<'a>>,
+    /// The byte offset where the token being lexed starts.
+    lexeme_start: usize,
+    line: usize,
+    /// Whether whitespace and comments are yielded as tokens rather than skipped.
+    trivia: bool,
+    /// Whether the `Eof` token has been yielded already.
+    finished: bool,
+}
+
+impl<'a> Lexer<'a> {
+    pub(crate) fn new(source: &'a str) -> Self {
+        Self {
+            source,
+            characters: indexed_iterator(peek_nth(source.chars())),
+            lexeme_start: 0,
+            line: 1,
+            trivia: false,
+            finished: false,
+        }
+    }
+
+    /// Makes the lexer lossless: whitespace and comments are yielded as trivia tokens too, so that
+    /// the tokens cover every byte of the source.
+    pub(crate) fn with_trivia(mut self) -> Self {
+        self.trivia = true;
+        self
+    }
+}
 
-impl Lexer {
-    pub(crate) fn lex(source: &str) -> Tokens {
-        let mut tokens = vec![];
-        let mut lexeme_start = 0;
-        let mut line = 1;
+impl<'a> Iterator for Lexer<'a> {
+    type Item = LexResult<'a>;
 
-        // TODO: Iterates over Unicode Scalar Values instead of grapheme clusters.
-        let mut characters = indexed_iterator(peek_nth(source.chars()));
-        while let Some(char) = characters.next() {
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.finished {
+            return None;
+        }
+        let source = self.source;
+        let Self {
+            characters,
+            lexeme_start,
+            line,
+            trivia,
+            ..
+        } = self;
+        loop {
+            let (start_line, start_column) = (*line, characters.current_column());
+            let Some(char) = characters.next() else {
+                break;
+            };
+            let token_start = *lexeme_start;
+            let span_to = move |end: usize| Span::new(token_start, end, start_line, start_column);
             let token_kind = match char {
                 '(' => Ok(TokenKind::LeftParen {
-                    lexeme: &source[lexeme_start..characters.current_idx()],
+                    lexeme: &source[*lexeme_start..characters.current_idx()],
                 }),
                 ')' => Ok(TokenKind::RightParen {
-                    lexeme: &source[lexeme_start..characters.current_idx()],
+                    lexeme: &source[*lexeme_start..characters.current_idx()],
                 }),
                 '{' => Ok(TokenKind::LeftBrace {
-                    lexeme: &source[lexeme_start..characters.current_idx()],
+                    lexeme: &source[*lexeme_start..characters.current_idx()],
                 }),
                 '}' => Ok(TokenKind::RightBrace {
-                    lexeme: &source[lexeme_start..characters.current_idx()],
+                    lexeme: &source[*lexeme_start..characters.current_idx()],
                 }),
                 ',' => Ok(TokenKind::Comma {
-                    lexeme: &source[lexeme_start..characters.current_idx()],
+                    lexeme: &source[*lexeme_start..characters.current_idx()],
                 }),
                 '.' => Ok(TokenKind::Dot {
-                    lexeme: &source[lexeme_start..characters.current_idx()],
+                    lexeme: &source[*lexeme_start..characters.current_idx()],
                 }),
                 '-' => Ok(TokenKind::Minus {
-                    lexeme: &source[lexeme_start..characters.current_idx()],
+                    lexeme: &source[*lexeme_start..characters.current_idx()],
                 }),
                 '+' => Ok(TokenKind::Plus {
-                    lexeme: &source[lexeme_start..characters.current_idx()],
+                    lexeme: &source[*lexeme_start..characters.current_idx()],
                 }),
                 ';' => Ok(TokenKind::Semicolon {
-                    lexeme: &source[lexeme_start..characters.current_idx()],
+                    lexeme: &source[*lexeme_start..characters.current_idx()],
                 }),
                 '*' => Ok(TokenKind::Star {
-                    lexeme: &source[lexeme_start..characters.current_idx()],
+                    lexeme: &source[*lexeme_start..characters.current_idx()],
                 }),
-                c if c.is_ascii_alphabetic() || c == '_' => {
-                    while characters
-                        .next_if(|c| c.is_ascii_alphanumeric() || *c == '_')
-                        .is_some()
-                    {}
+                'r' if starts_raw_string(characters) => {
+                    let mut hashes = 0;
+                    while characters.next_if_eq(&'#').is_some() {
+                        hashes += 1;
+                    }
+                    // Consume the opening '"'
+                    characters.next();
+                    loop {
+                        match characters.next() {
+                            None => {
+                                break Err(LexicalError::UnterminatedString {
+                                    span: span_to(characters.current_idx()),
+                                })
+                            }
+                            Some('"')
+                                if (0..hashes).all(|n| characters.peek_nth(n) == Some(&'#')) =>
+                            {
+                                for _ in 0..hashes {
+                                    characters.next();
+                                }
+                                break Ok(TokenKind::String {
+                                    lexeme: &source[*lexeme_start..characters.current_idx()],
+                                });
+                            }
+                            Some('\n') => {
+                                *line += 1;
+                            }
+                            Some(_) => {}
+                        }
+                    }
+                }
+                c if is_identifier_start(c) => {
+                    while characters.next_if(|c| is_identifier_continue(*c)).is_some() {}
 
                     if let Some(token_kind) =
-                        RESERVED_KEYWORDS.get(&source[lexeme_start..characters.current_idx()])
+                        RESERVED_KEYWORDS.get(&source[*lexeme_start..characters.current_idx()])
                     {
                         Ok(*token_kind)
                     } else {
                         Ok(TokenKind::Identifier {
-                            lexeme: &source[lexeme_start..characters.current_idx()],
+                            lexeme: &source[*lexeme_start..characters.current_idx()],
                         })
                     }
                 }
                 c if c.is_ascii_digit() => {
-                    while characters.next_if(|c| c.is_ascii_digit()).is_some() {}
-
-                    if let Some('.') = characters.peek() {
-                        match characters.peek_nth(1) {
-                            Some(c) if c.is_ascii_digit() => {
-                                // Consume the '.'
-                                characters.next();
-                                while characters.next_if(|c| c.is_ascii_digit()).is_some() {}
-                            }
-                            _ => (),
-                        }
-                    }
-                    match &source[lexeme_start..characters.current_idx()].parse::<f64>() {
-                        Ok(v) => Ok(TokenKind::Number { lexeme: *v }),
-                        Err(_) => Err(LexicalError::NaN { line }),
-                    }
+                    number(characters, c, source, *lexeme_start, *line, start_column)
                 }
                 '"' => loop {
                     match characters.next() {
-                        None => break Err(LexicalError::UnterminatedString { line }),
-                        Some(new_char) => {
-                            if new_char == '\n' {
-                                line += 1;
-                            } else if new_char == '"' {
-                                break Ok(TokenKind::String {
-                                    lexeme: &source[lexeme_start..characters.current_idx()],
-                                });
-                            }
+                        None => {
+                            break Err(LexicalError::UnterminatedString {
+                                span: span_to(characters.current_idx()),
+                            })
+                        }
+                        Some('"') => {
+                            let lexeme = &source[*lexeme_start..characters.current_idx()];
+                            break match unescape(&lexeme[1..lexeme.len() - 1]) {
+                                Ok(_) => Ok(TokenKind::String { lexeme }),
+                                Err(escape) => {
+                                    let start = *lexeme_start + 1 + escape.start;
+                                    let end = *lexeme_start + 1 + escape.end;
+                                    Err(LexicalError::InvalidEscape {
+                                        sequence: source[start..end].to_string(),
+                                        span: span_between(source, start, end),
+                                    })
+                                }
+                            };
+                        }
+                        Some('\\') => {
+                            // Skip escaped quotes and backslashes so that they don't end the string.
+                            characters.next_if(|c| matches!(c, '"' | '\\'));
                         }
+                        Some('\n') => {
+                            *line += 1;
+                        }
+                        Some(_) => {}
                     }
                 },
                 '!' => {
                     if characters.next_if_eq(&'=').is_some() {
                         Ok(TokenKind::BangEqual {
-                            lexeme: &source[lexeme_start..characters.current_idx()],
+                            lexeme: &source[*lexeme_start..characters.current_idx()],
                         })
                     } else {
                         Ok(TokenKind::Bang {
-                            lexeme: &source[lexeme_start..characters.current_idx()],
+                            lexeme: &source[*lexeme_start..characters.current_idx()],
                         })
                     }
                 }
                 '=' => {
                     if characters.next_if_eq(&'=').is_some() {
                         Ok(TokenKind::EqualEqual {
-                            lexeme: &source[lexeme_start..characters.current_idx()],
+                            lexeme: &source[*lexeme_start..characters.current_idx()],
                         })
                     } else {
                         Ok(TokenKind::Equal {
-                            lexeme: &source[lexeme_start..characters.current_idx()],
+                            lexeme: &source[*lexeme_start..characters.current_idx()],
                         })
                     }
                 }
                 '<' => {
                     if characters.next_if_eq(&'=').is_some() {
                         Ok(TokenKind::LessEqual {
-                            lexeme: &source[lexeme_start..characters.current_idx()],
+                            lexeme: &source[*lexeme_start..characters.current_idx()],
                         })
                     } else {
                         Ok(TokenKind::Less {
-                            lexeme: &source[lexeme_start..characters.current_idx()],
+                            lexeme: &source[*lexeme_start..characters.current_idx()],
                         })
                     }
                 }
                 '>' => {
                     if characters.next_if_eq(&'=').is_some() {
                         Ok(TokenKind::GreaterEqual {
-                            lexeme: &source[lexeme_start..characters.current_idx()],
+                            lexeme: &source[*lexeme_start..characters.current_idx()],
                         })
                     } else {
                         Ok(TokenKind::Greater {
-                            lexeme: &source[lexeme_start..characters.current_idx()],
+                            lexeme: &source[*lexeme_start..characters.current_idx()],
                         })
                     }
                 }
                 '/' => {
                     if characters.next_if_eq(&'/').is_some() {
-                        // Discard comments
                         'comment: while let Some(c) = characters.peek() {
                             if *c == '\n' {
                                 // Newlines are handled separately, don't consume them here
@@ -177,145 +259,456 @@ impl Lexer {
                                 characters.next();
                             }
                         }
-                        lexeme_start = characters.current_idx();
-                        continue;
+                        Ok(TokenKind::Comment {
+                            lexeme: &source[*lexeme_start..characters.current_idx()],
+                        })
+                    } else if characters.next_if_eq(&'*').is_some() {
+                        // Block comments may contain further block comments
+                        let mut depth = 1;
+                        while depth > 0 {
+                            match characters.next() {
+                                None => break,
+                                Some('*') if characters.next_if_eq(&'/').is_some() => depth -= 1,
+                                Some('/') if characters.next_if_eq(&'*').is_some() => depth += 1,
+                                Some('\n') => {
+                                    *line += 1;
+                                }
+                                Some(_) => {}
+                            }
+                        }
+                        if depth > 0 {
+                            Err(LexicalError::UnterminatedComment {
+                                span: span_to(characters.current_idx()),
+                            })
+                        } else {
+                            Ok(TokenKind::Comment {
+                                lexeme: &source[*lexeme_start..characters.current_idx()],
+                            })
+                        }
                     } else {
                         Ok(TokenKind::Slash {
-                            lexeme: &source[lexeme_start..characters.current_idx()],
+                            lexeme: &source[*lexeme_start..characters.current_idx()],
                         })
                     }
                 }
-                '\t' | ' ' | '\r' => {
-                    lexeme_start = characters.current_idx();
-                    continue;
-                }
-                '\n' => {
-                    line += 1;
-                    lexeme_start = characters.current_idx();
-                    continue;
+                '\t' | ' ' | '\r' | '\n' => {
+                    let mut whitespace = Some(char);
+                    while let Some(c) = whitespace {
+                        if c == '\n' {
+                            *line += 1;
+                        }
+                        whitespace = characters.next_if(|c| matches!(c, '\t' | ' ' | '\r' | '\n'));
+                    }
+                    Ok(TokenKind::Whitespace {
+                        lexeme: &source[*lexeme_start..characters.current_idx()],
+                    })
                 }
-                _ => Err(LexicalError::UnexpectedCharacter { char, line }),
+                _ => Err(LexicalError::UnexpectedCharacter {
+                    char,
+                    span: span_to(characters.current_idx()),
+                }),
             };
 
-            let lex_result = match token_kind {
-                Ok(token_kind) => {
-                    lexeme_start = characters.current_idx();
-                    Ok(Token::new(token_kind, line))
+            // Also skip past invalid input so that lexing resumes cleanly after an error.
+            let span = span_to(characters.current_idx());
+            *lexeme_start = characters.current_idx();
+            if !*trivia && matches!(token_kind, Ok(kind) if kind.is_trivia()) {
+                continue;
+            }
+            return Some(token_kind.map(|token_kind| Token::new(token_kind, span)));
+        }
+
+        self.finished = true;
+        Some(Ok(Token::new(
+            TokenKind::Eof,
+            Span::new(
+                self.lexeme_start,
+                self.lexeme_start,
+                self.line,
+                self.characters.current_column(),
+            ),
+        )))
+    }
+}
+
+/// The column of the character at byte offset `offset` of `source`, counted in characters from
+/// the start of its line at byte offset `line_start`.
+fn column(
+    source: &str,
+    line_start: usize,
+    offset: usize,
+) -> usize {
+    source[line_start..offset].chars().count() + 1
+}
+
+/// The span of `source[start..end]`.
+pub(crate) fn span_between(
+    source: &str,
+    start: usize,
+    end: usize,
+) -> Span {
+    let line_start = source[..start].rfind('\n').map_or(0, |newline| newline + 1);
+    Span::new(
+        start,
+        end,
+        source[..start].matches('\n').count() + 1,
+        column(source, line_start, start),
+    )
+}
+
+/// Lexes the rest of a number literal whose first digit `first` was just consumed: either a
+/// `0x1F`, `0b1010` or `0o17` integer or a decimal such as `1_000`, `2.5` or `1e-9`.
+fn number<'a, I>(
+    characters: &mut IndexedPeekNth<I>,
+    first: char,
+    source: &'a str,
+    start: usize,
+    line: usize,
+    start_column: usize,
+) -> Result<TokenKind<'a>, LexicalError>
+where
+    I: Iterator<Item = char>,
+{
+    // Number literals never span several lines.
+    let span = |from: usize, to: usize| {
+        Span::new(
+            from,
+            to,
+            line,
+            start_column + source[start..from].chars().count(),
+        )
+    };
+
+    let radix = match characters.peek() {
+        Some('x') if first == '0' => Some(16),
+        Some('o') if first == '0' => Some(8),
+        Some('b') if first == '0' => Some(2),
+        _ => None,
+    };
+    if let Some(radix) = radix {
+        // Consume the prefix letter
+        characters.next();
+        let digits_start = characters.current_idx();
+        while characters
+            .next_if(|c| c.is_ascii_alphanumeric() || *c == '_')
+            .is_some()
+        {}
+        let digits = &source[digits_start..characters.current_idx()];
+        if digits.is_empty() {
+            return Err(LexicalError::MissingDigits {
+                span: span(start, characters.current_idx()),
+            });
+        }
+        let mut value = 0.0;
+        for (offset, c) in digits.char_indices().filter(|(_, c)| *c != '_') {
+            let digit = c.to_digit(radix).ok_or(LexicalError::InvalidDigit {
+                digit: c,
+                radix,
+                span: span(digits_start + offset, digits_start + offset + c.len_utf8()),
+            })?;
+            value = value * f64::from(radix) + f64::from(digit);
+        }
+        check_digit_separators(digits, radix, digits_start, span)?;
+        return Ok(TokenKind::Number { lexeme: value });
+    }
+
+    decimal_digits(characters);
+    if let Some('.') = characters.peek() {
+        match characters.peek_nth(1) {
+            Some(c) if c.is_ascii_digit() => {
+                // Consume the '.'
+                characters.next();
+                decimal_digits(characters);
+            }
+            _ => (),
+        }
+    }
+    if characters.next_if(|c| matches!(c, 'e' | 'E')).is_some() {
+        characters.next_if(|c| matches!(c, '+' | '-'));
+        let exponent_start = characters.current_idx();
+        decimal_digits(characters);
+        if characters.current_idx() == exponent_start {
+            return Err(LexicalError::MissingExponentDigits {
+                span: span(start, characters.current_idx()),
+            });
+        }
+    }
+    let literal = &source[start..characters.current_idx()];
+    check_digit_separators(literal, 10, start, span)?;
+    let value = literal
+        .replace('_', "")
+        .parse()
+        .expect("decimal literals without separators are valid floats");
+    Ok(TokenKind::Number { lexeme: value })
+}
+
+/// Consumes decimal digits and the `_` separators among them, including misplaced ones for
+/// [`check_digit_separators`] to reject. Underscores followed by an identifier are left alone, so
+/// that `1_foo` is still the number `1` followed by `_foo`, while `1_`, `1_.5` and `2.5_e3` are
+/// single malformed literals.
+fn decimal_digits<I>(characters: &mut IndexedPeekNth<I>)
+where
+    I: Iterator<Item = char>,
+{
+    loop {
+        let consume = match characters.peek().copied() {
+            Some(c) if c.is_ascii_digit() => true,
+            Some('_') => {
+                let after = 1
+                    + (1..)
+                        .take_while(|n| characters.peek_nth(*n) == Some(&'_'))
+                        .count();
+                match characters.peek_nth(after).copied() {
+                    Some('e' | 'E') => match characters.peek_nth(after + 1) {
+                        Some('+' | '-') => characters
+                            .peek_nth(after + 2)
+                            .is_some_and(char::is_ascii_digit),
+                        next => next.is_some_and(char::is_ascii_digit),
+                    },
+                    Some(c) => c.is_ascii_digit() || !is_identifier_continue(c),
+                    None => true,
                 }
-                Err(lexical_error) => Err(lexical_error),
-            };
-            tokens.push(lex_result);
+            }
+            _ => false,
+        };
+        if !consume {
+            break;
+        }
+        characters.next();
+    }
+}
+
+/// Checks that every `_` in the number literal `literal`, found at byte offset `offset` of the
+/// source, sits between two digits of base `radix`. Exponent markers aren't digits, so `1e_5` is
+/// rejected.
+fn check_digit_separators(
+    literal: &str,
+    radix: u32,
+    offset: usize,
+    span: impl Fn(usize, usize) -> Span,
+) -> Result<(), LexicalError> {
+    let chars = literal.char_indices().collect_vec();
+    for (i, &(idx, c)) in chars.iter().enumerate() {
+        let is_digit =
+            |neighbour: Option<&(usize, char)>| neighbour.is_some_and(|(_, c)| c.is_digit(radix));
+        if c == '_' && !(i > 0 && is_digit(chars.get(i - 1)) && is_digit(chars.get(i + 1))) {
+            return Err(LexicalError::MisplacedDigitSeparator {
+                span: span(offset + idx, offset + idx + 1),
+            });
+        }
+    }
+    Ok(())
+}
+
+/// Whether the `r` just consumed starts a raw string, either `r"..."` or `r#"..."#` with any
+/// number of `#` around the quotes.
+fn starts_raw_string<I>(characters: &mut IndexedPeekNth<I>) -> bool
+where
+    I: Iterator<Item = char>,
+{
+    let hashes = (0..)
+        .take_while(|n| characters.peek_nth(*n) == Some(&'#'))
+        .count();
+    characters.peek_nth(hashes) == Some(&'"')
+}
+
+/// The value of the string literal `lexeme`, which has already been accepted by the lexer.
+pub(crate) fn string_value(lexeme: &str) -> Cow<'_, str> {
+    match lexeme.strip_prefix('r') {
+        Some(raw) => {
+            let hashes = raw.len() - raw.trim_start_matches('#').len();
+            Cow::Borrowed(&raw[hashes + 1..raw.len() - hashes - 1])
         }
+        None => {
+            let contents = &lexeme[1..lexeme.len() - 1];
+            unescape(contents).unwrap_or(Cow::Borrowed(contents))
+        }
+    }
+}
 
-        tokens.push(Ok(Token::new(TokenKind::Eof, line)));
+/// Decodes the escape sequences in `contents`, the text between the quotes of a string literal.
+/// Fails with the byte range of the first escape sequence that is not supported.
+fn unescape(contents: &str) -> Result<Cow<'_, str>, Range<usize>> {
+    if !contents.contains('\\') {
+        return Ok(Cow::Borrowed(contents));
+    }
+    let mut value = String::with_capacity(contents.len());
+    let mut chars = contents.char_indices().peekable();
+    while let Some((start, c)) = chars.next() {
+        if c != '\\' {
+            value.push(c);
+            continue;
+        }
+        let escaped = match chars.next() {
+            Some((_, 'n')) => Some('\n'),
+            Some((_, 't')) => Some('\t'),
+            Some((_, '"')) => Some('"'),
+            Some((_, '\\')) => Some('\\'),
+            Some((_, 'u')) => unicode_escape(&mut chars),
+            _ => None,
+        };
+        let end = chars.peek().map_or(contents.len(), |(idx, _)| *idx);
+        value.push(escaped.ok_or(start..end)?);
+    }
+    Ok(Cow::Owned(value))
+}
 
-        Tokens::new(tokens)
+/// Decodes the `{...}` part of a `\u{...}` escape: one to six hex digits naming a Unicode scalar
+/// value.
+fn unicode_escape(chars: &mut Peekable<CharIndices>) -> Option<char> {
+    chars.next_if(|(_, c)| *c == '{')?;
+    let mut code = String::new();
+    while let Some((_, digit)) = chars.next_if(|(_, c)| c.is_ascii_hexdigit()) {
+        code.push(digit);
+    }
+    chars.next_if(|(_, c)| *c == '}')?;
+    if code.is_empty() || code.len() > 6 {
+        return None;
     }
+    u32::from_str_radix(&code, 16).ok().and_then(char::from_u32)
+}
+
+#[cfg(not(feature = "unicode-identifiers"))]
+fn is_identifier_start(c: char) -> bool {
+    c.is_ascii_alphabetic() || c == '_'
+}
+
+#[cfg(not(feature = "unicode-identifiers"))]
+fn is_identifier_continue(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_'
+}
+
+#[cfg(feature = "unicode-identifiers")]
+fn is_identifier_start(c: char) -> bool {
+    unicode_ident::is_xid_start(c) || c == '_'
+}
+
+#[cfg(feature = "unicode-identifiers")]
+fn is_identifier_continue(c: char) -> bool {
+    unicode_ident::is_xid_continue(c)
 }
 
 pub(crate) fn indexed_iterator<I>(iterable: PeekNth<I>) -> IndexedPeekNth<I>
 where
-    I: Iterator,
+    I: Iterator<Item = char>,
 {
     IndexedPeekNth {
         current_idx: 0,
+        current_column: 1,
         iter: iterable,
     }
 }
 
-pub(crate) struct IndexedPeekNth<I: Iterator> {
+/// A peekable iterator over the characters of a source that keeps track of the byte offset and the
+/// column of the next character.
+#[derive(Debug)]
+pub(crate) struct IndexedPeekNth<I: Iterator<Item = char>> {
     current_idx: usize,
+    current_column: usize,
     iter: PeekNth<I>,
 }
 
 impl<I> IndexedPeekNth<I>
 where
-    I: Iterator,
+    I: Iterator<Item = char>,
 {
+    /// The byte offset of the next character, or the length of the source once it is exhausted.
     pub(crate) fn current_idx(&self) -> usize {
         self.current_idx
     }
 
-    fn next_if(
+    /// The column of the next character, counted in characters from the start of its line.
+    pub(crate) fn current_column(&self) -> usize {
+        self.current_column
+    }
+
+    fn advance(
         &mut self,
-        func: impl FnOnce(&I::Item) -> bool,
-    ) -> Option<I::Item> {
-        let item = self.iter.next_if(func);
-        if item.is_some() {
-            self.current_idx += 1;
+        item: Option<char>,
+    ) -> Option<char> {
+        if let Some(c) = item {
+            self.current_idx += c.len_utf8();
+            self.current_column = if c == '\n' {
+                1
+            } else {
+                self.current_column + 1
+            };
         }
         item
     }
 
-    fn next_if_eq<T>(
+    fn next_if(
+        &mut self,
+        func: impl FnOnce(&char) -> bool,
+    ) -> Option<char> {
+        let item = self.iter.next_if(func);
+        self.advance(item)
+    }
+
+    fn next_if_eq(
         &mut self,
-        expected: &T,
-    ) -> Option<I::Item>
-    where
-        T: ?Sized,
-        I::Item: PartialEq<T>,
-    {
+        expected: &char,
+    ) -> Option<char> {
         let item = self.iter.next_if_eq(expected);
-        if item.is_some() {
-            self.current_idx += 1;
-        }
-        item
+        self.advance(item)
     }
 
-    fn peek(&mut self) -> Option<&I::Item> {
+    fn peek(&mut self) -> Option<&char> {
         self.iter.peek()
     }
 
     fn peek_nth(
         &mut self,
         n: usize,
-    ) -> Option<&I::Item> {
+    ) -> Option<&char> {
         self.iter.peek_nth(n)
     }
 }
 
-impl<I, U> Iterator for IndexedPeekNth<I>
+impl<I> Iterator for IndexedPeekNth<I>
 where
-    I: Iterator<Item = U>,
+    I: Iterator<Item = char>,
 {
-    type Item = U;
+    type Item = char;
 
     fn next(&mut self) -> Option<Self::Item> {
-        self.current_idx += 1;
-        self.iter.next()
+        let item = self.iter.next();
+        self.advance(item)
     }
 }
 
 #[cfg(test)]
 mod test {
     use itertools::Itertools;
+    use proptest::prelude::*;
 
     use super::*;
+    use crate::token::LexResult;
 
     #[test]
     fn scanning_single_character_lexemes_works() {
         let input = "(){},.-+;=*!<>/";
-        let tokens = Lexer::lex(input);
+        let tokens = Lexer::new(input);
 
         assert_eq!(
-            tokens.into_iter().flatten().collect_vec(),
+            tokens.flatten().collect_vec(),
             vec![
-                Token::new(TokenKind::LeftParen { lexeme: "(" }, 1),
-                Token::new(TokenKind::RightParen { lexeme: ")" }, 1),
-                Token::new(TokenKind::LeftBrace { lexeme: "{" }, 1),
-                Token::new(TokenKind::RightBrace { lexeme: "}" }, 1),
-                Token::new(TokenKind::Comma { lexeme: "," }, 1),
-                Token::new(TokenKind::Dot { lexeme: "." }, 1),
-                Token::new(TokenKind::Minus { lexeme: "-" }, 1),
-                Token::new(TokenKind::Plus { lexeme: "+" }, 1),
-                Token::new(TokenKind::Semicolon { lexeme: ";" }, 1),
-                Token::new(TokenKind::Equal { lexeme: "=" }, 1),
-                Token::new(TokenKind::Star { lexeme: "*" }, 1),
-                Token::new(TokenKind::Bang { lexeme: "!" }, 1),
-                Token::new(TokenKind::Less { lexeme: "<" }, 1),
-                Token::new(TokenKind::Greater { lexeme: ">" }, 1),
-                Token::new(TokenKind::Slash { lexeme: "/" }, 1),
-                Token::new(TokenKind::Eof, 1),
+                Token::new(TokenKind::LeftParen { lexeme: "(" }, Span::new(0, 1, 1, 1)),
+                Token::new(TokenKind::RightParen { lexeme: ")" }, Span::new(1, 2, 1, 2)),
+                Token::new(TokenKind::LeftBrace { lexeme: "{" }, Span::new(2, 3, 1, 3)),
+                Token::new(TokenKind::RightBrace { lexeme: "}" }, Span::new(3, 4, 1, 4)),
+                Token::new(TokenKind::Comma { lexeme: "," }, Span::new(4, 5, 1, 5)),
+                Token::new(TokenKind::Dot { lexeme: "." }, Span::new(5, 6, 1, 6)),
+                Token::new(TokenKind::Minus { lexeme: "-" }, Span::new(6, 7, 1, 7)),
+                Token::new(TokenKind::Plus { lexeme: "+" }, Span::new(7, 8, 1, 8)),
+                Token::new(TokenKind::Semicolon { lexeme: ";" }, Span::new(8, 9, 1, 9)),
+                Token::new(TokenKind::Equal { lexeme: "=" }, Span::new(9, 10, 1, 10)),
+                Token::new(TokenKind::Star { lexeme: "*" }, Span::new(10, 11, 1, 11)),
+                Token::new(TokenKind::Bang { lexeme: "!" }, Span::new(11, 12, 1, 12)),
+                Token::new(TokenKind::Less { lexeme: "<" }, Span::new(12, 13, 1, 13)),
+                Token::new(TokenKind::Greater { lexeme: ">" }, Span::new(13, 14, 1, 14)),
+                Token::new(TokenKind::Slash { lexeme: "/" }, Span::new(14, 15, 1, 15)),
+                Token::new(TokenKind::Eof, Span::new(15, 15, 1, 16)),
             ]
         )
     }
@@ -323,20 +716,26 @@ mod test {
     #[test]
     fn scanning_double_character_lexemes_works() {
         let input = " != <= >= == = =\n!\n=";
-        let tokens = Lexer::lex(input);
+        let tokens = Lexer::new(input);
 
         assert_eq!(
-            tokens.into_iter().flatten().collect_vec(),
+            tokens.flatten().collect_vec(),
             vec![
-                Token::new(TokenKind::BangEqual { lexeme: "!=" }, 1),
-                Token::new(TokenKind::LessEqual { lexeme: "<=" }, 1),
-                Token::new(TokenKind::GreaterEqual { lexeme: ">=" }, 1),
-                Token::new(TokenKind::EqualEqual { lexeme: "==" }, 1),
-                Token::new(TokenKind::Equal { lexeme: "=" }, 1),
-                Token::new(TokenKind::Equal { lexeme: "=" }, 1),
-                Token::new(TokenKind::Bang { lexeme: "!" }, 2),
-                Token::new(TokenKind::Equal { lexeme: "=" }, 3),
-                Token::new(TokenKind::Eof, 3),
+                Token::new(TokenKind::BangEqual { lexeme: "!=" }, Span::new(1, 3, 1, 2)),
+                Token::new(TokenKind::LessEqual { lexeme: "<=" }, Span::new(4, 6, 1, 5)),
+                Token::new(
+                    TokenKind::GreaterEqual { lexeme: ">=" },
+                    Span::new(7, 9, 1, 8)
+                ),
+                Token::new(
+                    TokenKind::EqualEqual { lexeme: "==" },
+                    Span::new(10, 12, 1, 11)
+                ),
+                Token::new(TokenKind::Equal { lexeme: "=" }, Span::new(13, 14, 1, 14)),
+                Token::new(TokenKind::Equal { lexeme: "=" }, Span::new(15, 16, 1, 16)),
+                Token::new(TokenKind::Bang { lexeme: "!" }, Span::new(17, 18, 2, 1)),
+                Token::new(TokenKind::Equal { lexeme: "=" }, Span::new(19, 20, 3, 1)),
+                Token::new(TokenKind::Eof, Span::new(20, 20, 3, 2)),
             ]
         )
     }
@@ -344,17 +743,23 @@ mod test {
     #[test]
     fn ignoring_whitespaces_works() {
         let input = "(   \r)    {\t     }\n\n\n\n!";
-        let tokens = Lexer::lex(input);
+        let tokens = Lexer::new(input);
 
         assert_eq!(
-            tokens.into_iter().flatten().collect_vec(),
+            tokens.flatten().collect_vec(),
             vec![
-                Token::new(TokenKind::LeftParen { lexeme: "(" }, 1),
-                Token::new(TokenKind::RightParen { lexeme: ")" }, 1),
-                Token::new(TokenKind::LeftBrace { lexeme: "{" }, 1),
-                Token::new(TokenKind::RightBrace { lexeme: "}" }, 1),
-                Token::new(TokenKind::Bang { lexeme: "!" }, 5),
-                Token::new(TokenKind::Eof, 5),
+                Token::new(TokenKind::LeftParen { lexeme: "(" }, Span::new(0, 1, 1, 1)),
+                Token::new(TokenKind::RightParen { lexeme: ")" }, Span::new(5, 6, 1, 6)),
+                Token::new(
+                    TokenKind::LeftBrace { lexeme: "{" },
+                    Span::new(10, 11, 1, 11)
+                ),
+                Token::new(
+                    TokenKind::RightBrace { lexeme: "}" },
+                    Span::new(17, 18, 1, 18)
+                ),
+                Token::new(TokenKind::Bang { lexeme: "!" }, Span::new(22, 23, 5, 1)),
+                Token::new(TokenKind::Eof, Span::new(23, 23, 5, 2)),
             ]
         )
     }
@@ -362,18 +767,18 @@ mod test {
     #[test]
     fn scanning_basic_valid_strings_works() {
         let input = "\"this is a string\"";
-        let tokens = Lexer::lex(input);
+        let tokens = Lexer::new(input);
 
         assert_eq!(
-            tokens.into_iter().flatten().collect_vec(),
+            tokens.flatten().collect_vec(),
             vec![
                 Token::new(
                     TokenKind::String {
                         lexeme: r#""this is a string""#
                     },
-                    1
+                    Span::new(0, 18, 1, 1)
                 ),
-                Token::new(TokenKind::Eof, 1),
+                Token::new(TokenKind::Eof, Span::new(18, 18, 1, 19)),
             ]
         )
     }
@@ -381,18 +786,18 @@ mod test {
     #[test]
     fn scanning_multiline_strings_works() {
         let input = "\"this is a string\nacross multiple lines\"";
-        let tokens = Lexer::lex(input);
+        let tokens = Lexer::new(input);
 
         assert_eq!(
-            tokens.into_iter().flatten().collect_vec(),
+            tokens.flatten().collect_vec(),
             vec![
                 Token::new(
                     TokenKind::String {
                         lexeme: "\"this is a string\nacross multiple lines\""
                     },
-                    2
+                    Span::new(0, 40, 1, 1)
                 ),
-                Token::new(TokenKind::Eof, 2),
+                Token::new(TokenKind::Eof, Span::new(40, 40, 2, 23)),
             ]
         )
     }
@@ -400,13 +805,15 @@ mod test {
     #[test]
     fn scanning_unterminated_string_produces_error() {
         let input = "\"this is not a string";
-        let tokens = Lexer::lex(input);
+        let tokens = Lexer::new(input);
 
         assert_eq!(
-            tokens.into_iter().collect_vec(),
+            tokens.collect_vec(),
             vec![
-                Err(LexicalError::UnterminatedString { line: 1 }),
-                Ok(Token::new(TokenKind::Eof, 1)),
+                Err(LexicalError::UnterminatedString {
+                    span: Span::new(0, 21, 1, 1)
+                }),
+                Ok(Token::new(TokenKind::Eof, Span::new(21, 21, 1, 22))),
             ]
         )
     }
@@ -414,15 +821,24 @@ mod test {
     #[test]
     fn scanning_valid_integer_works() {
         let input = "  1 20 4212";
-        let tokens = Lexer::lex(input);
+        let tokens = Lexer::new(input);
 
         assert_eq!(
-            tokens.into_iter().collect_vec(),
+            tokens.collect_vec(),
             vec![
-                Ok(Token::new(TokenKind::Number { lexeme: 1.0 }, 1)),
-                Ok(Token::new(TokenKind::Number { lexeme: 20.0 }, 1)),
-                Ok(Token::new(TokenKind::Number { lexeme: 4212.0 }, 1)),
-                Ok(Token::new(TokenKind::Eof, 1)),
+                Ok(Token::new(
+                    TokenKind::Number { lexeme: 1.0 },
+                    Span::new(2, 3, 1, 3)
+                )),
+                Ok(Token::new(
+                    TokenKind::Number { lexeme: 20.0 },
+                    Span::new(4, 6, 1, 5)
+                )),
+                Ok(Token::new(
+                    TokenKind::Number { lexeme: 4212.0 },
+                    Span::new(7, 11, 1, 8)
+                )),
+                Ok(Token::new(TokenKind::Eof, Span::new(11, 11, 1, 12))),
             ]
         )
     }
@@ -430,15 +846,24 @@ mod test {
     #[test]
     fn scanning_valid_fractional_number_works() {
         let input = "  0.0001 2.0 421.2";
-        let tokens = Lexer::lex(input);
+        let tokens = Lexer::new(input);
 
         assert_eq!(
-            tokens.into_iter().collect_vec(),
+            tokens.collect_vec(),
             vec![
-                Ok(Token::new(TokenKind::Number { lexeme: 0.0001 }, 1)),
-                Ok(Token::new(TokenKind::Number { lexeme: 2.0 }, 1)),
-                Ok(Token::new(TokenKind::Number { lexeme: 421.2 }, 1)),
-                Ok(Token::new(TokenKind::Eof, 1)),
+                Ok(Token::new(
+                    TokenKind::Number { lexeme: 0.0001 },
+                    Span::new(2, 8, 1, 3)
+                )),
+                Ok(Token::new(
+                    TokenKind::Number { lexeme: 2.0 },
+                    Span::new(9, 12, 1, 10)
+                )),
+                Ok(Token::new(
+                    TokenKind::Number { lexeme: 421.2 },
+                    Span::new(13, 18, 1, 14)
+                )),
+                Ok(Token::new(TokenKind::Eof, Span::new(18, 18, 1, 19))),
             ]
         )
     }
@@ -446,51 +871,181 @@ mod test {
     #[test]
     fn scanning_invalid_fractional_number_works() {
         let input = "  0. 2123. .2 .0012";
-        let tokens = Lexer::lex(input);
+        let tokens = Lexer::new(input);
+
+        assert_eq!(
+            tokens.collect_vec(),
+            vec![
+                Ok(Token::new(
+                    TokenKind::Number { lexeme: 0.0 },
+                    Span::new(2, 3, 1, 3)
+                )),
+                Ok(Token::new(
+                    TokenKind::Dot { lexeme: "." },
+                    Span::new(3, 4, 1, 4)
+                )),
+                Ok(Token::new(
+                    TokenKind::Number { lexeme: 2123.0 },
+                    Span::new(5, 9, 1, 6)
+                )),
+                Ok(Token::new(
+                    TokenKind::Dot { lexeme: "." },
+                    Span::new(9, 10, 1, 10)
+                )),
+                Ok(Token::new(
+                    TokenKind::Dot { lexeme: "." },
+                    Span::new(11, 12, 1, 12)
+                )),
+                Ok(Token::new(
+                    TokenKind::Number { lexeme: 2.0 },
+                    Span::new(12, 13, 1, 13)
+                )),
+                Ok(Token::new(
+                    TokenKind::Dot { lexeme: "." },
+                    Span::new(14, 15, 1, 15)
+                )),
+                Ok(Token::new(
+                    TokenKind::Number { lexeme: 12.0 },
+                    Span::new(15, 19, 1, 16)
+                )),
+                Ok(Token::new(TokenKind::Eof, Span::new(19, 19, 1, 20))),
+            ]
+        )
+    }
+
+    #[test]
+    fn scanning_extended_number_literals_works() {
+        let input = "0x1F 0b1010 0o17 1e-9 2.5E3 1_000_000 0xff_ff 3e+2";
+        let numbers = Lexer::new(input)
+            .flatten()
+            .filter_map(|token| match token.kind() {
+                TokenKind::Number { lexeme } => Some(lexeme),
+                _ => None,
+            })
+            .collect_vec();
+
+        assert_eq!(
+            numbers,
+            vec![31.0, 10.0, 15.0, 1e-9, 2500.0, 1_000_000.0, 65535.0, 300.0]
+        )
+    }
+
+    #[test]
+    fn scanning_malformed_number_literals_produces_precise_errors() {
+        let input = "0x 0b102 1e 1__0 0x_1 1e+ 1e_5 0xG_1";
+        let errors = Lexer::new(input).filter_map(Result::err).collect_vec();
 
         assert_eq!(
-            tokens.into_iter().collect_vec(),
+            errors,
             vec![
-                Ok(Token::new(TokenKind::Number { lexeme: 0.0 }, 1)),
-                Ok(Token::new(TokenKind::Dot { lexeme: "." }, 1)),
-                Ok(Token::new(TokenKind::Number { lexeme: 2123.0 }, 1)),
-                Ok(Token::new(TokenKind::Dot { lexeme: "." }, 1)),
-                Ok(Token::new(TokenKind::Dot { lexeme: "." }, 1)),
-                Ok(Token::new(TokenKind::Number { lexeme: 2.0 }, 1)),
-                Ok(Token::new(TokenKind::Dot { lexeme: "." }, 1)),
-                Ok(Token::new(TokenKind::Number { lexeme: 12.0 }, 1)),
-                Ok(Token::new(TokenKind::Eof, 1)),
+                LexicalError::MissingDigits {
+                    span: Span::new(0, 2, 1, 1)
+                },
+                LexicalError::InvalidDigit {
+                    digit: '2',
+                    radix: 2,
+                    span: Span::new(7, 8, 1, 8)
+                },
+                LexicalError::MissingExponentDigits {
+                    span: Span::new(9, 11, 1, 10)
+                },
+                LexicalError::MisplacedDigitSeparator {
+                    span: Span::new(13, 14, 1, 14)
+                },
+                LexicalError::MisplacedDigitSeparator {
+                    span: Span::new(19, 20, 1, 20)
+                },
+                LexicalError::MissingExponentDigits {
+                    span: Span::new(22, 25, 1, 23)
+                },
+                LexicalError::MisplacedDigitSeparator {
+                    span: Span::new(28, 29, 1, 29)
+                },
+                LexicalError::InvalidDigit {
+                    digit: 'G',
+                    radix: 16,
+                    span: Span::new(33, 34, 1, 34)
+                },
             ]
         )
     }
 
+    #[test]
+    fn scanning_trailing_digit_separators_rejects_the_whole_literal() {
+        let input = "1_ 1.5_;\n2.5_e3 1_.5 1_foo";
+        let errors = Lexer::new(input).filter_map(Result::err).collect_vec();
+        let tokens = Lexer::new(input).flatten().collect_vec();
+
+        assert_eq!(
+            errors,
+            vec![
+                LexicalError::MisplacedDigitSeparator {
+                    span: Span::new(1, 2, 1, 2)
+                },
+                LexicalError::MisplacedDigitSeparator {
+                    span: Span::new(6, 7, 1, 7)
+                },
+                LexicalError::MisplacedDigitSeparator {
+                    span: Span::new(12, 13, 2, 4)
+                },
+                LexicalError::MisplacedDigitSeparator {
+                    span: Span::new(17, 18, 2, 9)
+                },
+            ]
+        );
+        assert_eq!(
+            tokens,
+            vec![
+                Token::new(TokenKind::Semicolon { lexeme: ";" }, Span::new(7, 8, 1, 8)),
+                Token::new(TokenKind::Number { lexeme: 1.0 }, Span::new(21, 22, 2, 13)),
+                Token::new(
+                    TokenKind::Identifier { lexeme: "_foo" },
+                    Span::new(22, 26, 2, 14)
+                ),
+                Token::new(TokenKind::Eof, Span::new(26, 26, 2, 18)),
+            ]
+        );
+    }
+
     #[test]
     fn scanning_identifiers_works() {
         let input = "some_identifier _anotherOne als0 c1 0no 001_no ";
-        let tokens = Lexer::lex(input);
+        let tokens = Lexer::new(input);
 
         assert_eq!(
-            tokens.into_iter().flatten().collect_vec(),
+            tokens.flatten().collect_vec(),
             vec![
                 Token::new(
                     TokenKind::Identifier {
                         lexeme: "some_identifier"
                     },
-                    1
+                    Span::new(0, 15, 1, 1)
                 ),
                 Token::new(
                     TokenKind::Identifier {
                         lexeme: "_anotherOne"
                     },
-                    1
+                    Span::new(16, 27, 1, 17)
+                ),
+                Token::new(
+                    TokenKind::Identifier { lexeme: "als0" },
+                    Span::new(28, 32, 1, 29)
                 ),
-                Token::new(TokenKind::Identifier { lexeme: "als0" }, 1),
-                Token::new(TokenKind::Identifier { lexeme: "c1" }, 1),
-                Token::new(TokenKind::Number { lexeme: 0.0 }, 1),
-                Token::new(TokenKind::Identifier { lexeme: "no" }, 1),
-                Token::new(TokenKind::Number { lexeme: 1.0 }, 1),
-                Token::new(TokenKind::Identifier { lexeme: "_no" }, 1),
-                Token::new(TokenKind::Eof, 1),
+                Token::new(
+                    TokenKind::Identifier { lexeme: "c1" },
+                    Span::new(33, 35, 1, 34)
+                ),
+                Token::new(TokenKind::Number { lexeme: 0.0 }, Span::new(36, 37, 1, 37)),
+                Token::new(
+                    TokenKind::Identifier { lexeme: "no" },
+                    Span::new(37, 39, 1, 38)
+                ),
+                Token::new(TokenKind::Number { lexeme: 1.0 }, Span::new(40, 43, 1, 41)),
+                Token::new(
+                    TokenKind::Identifier { lexeme: "_no" },
+                    Span::new(43, 46, 1, 44)
+                ),
+                Token::new(TokenKind::Eof, Span::new(47, 47, 1, 48)),
             ]
         )
     }
@@ -498,28 +1053,43 @@ mod test {
     #[test]
     fn scanning_reserved_words_works() {
         let input = "and class else false for fun if nil or print return super this true var while";
-        let tokens = Lexer::lex(input);
+        let tokens = Lexer::new(input);
 
         assert_eq!(
-            tokens.into_iter().flatten().collect_vec(),
+            tokens.flatten().collect_vec(),
             vec![
-                Token::new(TokenKind::And { lexeme: "and" }, 1),
-                Token::new(TokenKind::Class { lexeme: "class" }, 1),
-                Token::new(TokenKind::Else { lexeme: "else" }, 1),
-                Token::new(TokenKind::False { lexeme: "false" }, 1),
-                Token::new(TokenKind::For { lexeme: "for" }, 1),
-                Token::new(TokenKind::Fun { lexeme: "fun" }, 1),
-                Token::new(TokenKind::If { lexeme: "if" }, 1),
-                Token::new(TokenKind::Nil { lexeme: "nil" }, 1),
-                Token::new(TokenKind::Or { lexeme: "or" }, 1),
-                Token::new(TokenKind::Print { lexeme: "print" }, 1),
-                Token::new(TokenKind::Return { lexeme: "return" }, 1),
-                Token::new(TokenKind::Super { lexeme: "super" }, 1),
-                Token::new(TokenKind::This { lexeme: "this" }, 1),
-                Token::new(TokenKind::True { lexeme: "true" }, 1),
-                Token::new(TokenKind::Var { lexeme: "var" }, 1),
-                Token::new(TokenKind::While { lexeme: "while" }, 1),
-                Token::new(TokenKind::Eof, 1),
+                Token::new(TokenKind::And { lexeme: "and" }, Span::new(0, 3, 1, 1)),
+                Token::new(TokenKind::Class { lexeme: "class" }, Span::new(4, 9, 1, 5)),
+                Token::new(TokenKind::Else { lexeme: "else" }, Span::new(10, 14, 1, 11)),
+                Token::new(
+                    TokenKind::False { lexeme: "false" },
+                    Span::new(15, 20, 1, 16)
+                ),
+                Token::new(TokenKind::For { lexeme: "for" }, Span::new(21, 24, 1, 22)),
+                Token::new(TokenKind::Fun { lexeme: "fun" }, Span::new(25, 28, 1, 26)),
+                Token::new(TokenKind::If { lexeme: "if" }, Span::new(29, 31, 1, 30)),
+                Token::new(TokenKind::Nil { lexeme: "nil" }, Span::new(32, 35, 1, 33)),
+                Token::new(TokenKind::Or { lexeme: "or" }, Span::new(36, 38, 1, 37)),
+                Token::new(
+                    TokenKind::Print { lexeme: "print" },
+                    Span::new(39, 44, 1, 40)
+                ),
+                Token::new(
+                    TokenKind::Return { lexeme: "return" },
+                    Span::new(45, 51, 1, 46)
+                ),
+                Token::new(
+                    TokenKind::Super { lexeme: "super" },
+                    Span::new(52, 57, 1, 53)
+                ),
+                Token::new(TokenKind::This { lexeme: "this" }, Span::new(58, 62, 1, 59)),
+                Token::new(TokenKind::True { lexeme: "true" }, Span::new(63, 67, 1, 64)),
+                Token::new(TokenKind::Var { lexeme: "var" }, Span::new(68, 71, 1, 69)),
+                Token::new(
+                    TokenKind::While { lexeme: "while" },
+                    Span::new(72, 77, 1, 73)
+                ),
+                Token::new(TokenKind::Eof, Span::new(77, 77, 1, 78)),
             ]
         )
     }
@@ -527,16 +1097,16 @@ mod test {
     #[test]
     fn scanning_multiple_lines_works() {
         let input = "(\n)\n{\n}\n";
-        let tokens = Lexer::lex(input);
+        let tokens = Lexer::new(input);
 
         assert_eq!(
-            tokens.into_iter().flatten().collect_vec(),
+            tokens.flatten().collect_vec(),
             vec![
-                Token::new(TokenKind::LeftParen { lexeme: "(" }, 1),
-                Token::new(TokenKind::RightParen { lexeme: ")" }, 2),
-                Token::new(TokenKind::LeftBrace { lexeme: "{" }, 3),
-                Token::new(TokenKind::RightBrace { lexeme: "}" }, 4),
-                Token::new(TokenKind::Eof, 5),
+                Token::new(TokenKind::LeftParen { lexeme: "(" }, Span::new(0, 1, 1, 1)),
+                Token::new(TokenKind::RightParen { lexeme: ")" }, Span::new(2, 3, 2, 1)),
+                Token::new(TokenKind::LeftBrace { lexeme: "{" }, Span::new(4, 5, 3, 1)),
+                Token::new(TokenKind::RightBrace { lexeme: "}" }, Span::new(6, 7, 4, 1)),
+                Token::new(TokenKind::Eof, Span::new(8, 8, 5, 1)),
             ]
         )
     }
@@ -544,17 +1114,345 @@ mod test {
     #[test]
     fn scanning_comments_works() {
         let input = "() // this is a comment\n{} // another one";
-        let tokens = Lexer::lex(input);
+        let tokens = Lexer::new(input);
+
+        assert_eq!(
+            tokens.flatten().collect_vec(),
+            vec![
+                Token::new(TokenKind::LeftParen { lexeme: "(" }, Span::new(0, 1, 1, 1)),
+                Token::new(TokenKind::RightParen { lexeme: ")" }, Span::new(1, 2, 1, 2)),
+                Token::new(
+                    TokenKind::LeftBrace { lexeme: "{" },
+                    Span::new(24, 25, 2, 1)
+                ),
+                Token::new(
+                    TokenKind::RightBrace { lexeme: "}" },
+                    Span::new(25, 26, 2, 2)
+                ),
+                Token::new(TokenKind::Eof, Span::new(41, 41, 2, 18)),
+            ]
+        )
+    }
+
+    #[test]
+    fn scanning_nested_block_comments_works() {
+        let input = "( /* a\n /* b */ c\n */ )\n/* x */*";
+        let tokens = Lexer::new(input);
+
+        assert_eq!(
+            tokens.flatten().collect_vec(),
+            vec![
+                Token::new(TokenKind::LeftParen { lexeme: "(" }, Span::new(0, 1, 1, 1)),
+                Token::new(
+                    TokenKind::RightParen { lexeme: ")" },
+                    Span::new(22, 23, 3, 5)
+                ),
+                Token::new(TokenKind::Star { lexeme: "*" }, Span::new(31, 32, 4, 8)),
+                Token::new(TokenKind::Eof, Span::new(32, 32, 4, 9)),
+            ]
+        )
+    }
+
+    #[test]
+    fn scanning_unterminated_block_comment_produces_error() {
+        let input = "/* outer /* inner */\n";
+        let tokens = Lexer::new(input);
+
+        assert_eq!(
+            tokens.collect_vec(),
+            vec![
+                Err(LexicalError::UnterminatedComment {
+                    span: Span::new(0, 21, 1, 1)
+                }),
+                Ok(Token::new(TokenKind::Eof, Span::new(21, 21, 2, 1))),
+            ]
+        )
+    }
+
+    #[test]
+    fn lexing_is_lazy_and_can_stop_at_the_first_error() {
+        let mut lexer = Lexer::new("1 @ \"never reached");
+
+        assert_eq!(
+            lexer.next(),
+            Some(Ok(Token::new(
+                TokenKind::Number { lexeme: 1.0 },
+                Span::new(0, 1, 1, 1)
+            )))
+        );
+        assert_eq!(
+            lexer.next(),
+            Some(Err(LexicalError::UnexpectedCharacter {
+                char: '@',
+                span: Span::new(2, 3, 1, 3)
+            }))
+        );
+    }
+
+    #[test]
+    fn scanning_with_trivia_keeps_whitespace_and_comments() {
+        let input = "a // note\n\t/* b */;";
+        let tokens = Lexer::new(input).with_trivia().flatten().collect_vec();
 
         assert_eq!(
-            tokens.into_iter().flatten().collect_vec(),
+            tokens,
             vec![
-                Token::new(TokenKind::LeftParen { lexeme: "(" }, 1),
-                Token::new(TokenKind::RightParen { lexeme: ")" }, 1),
-                Token::new(TokenKind::LeftBrace { lexeme: "{" }, 2),
-                Token::new(TokenKind::RightBrace { lexeme: "}" }, 2),
-                Token::new(TokenKind::Eof, 2),
+                Token::new(TokenKind::Identifier { lexeme: "a" }, Span::new(0, 1, 1, 1)),
+                Token::new(TokenKind::Whitespace { lexeme: " " }, Span::new(1, 2, 1, 2)),
+                Token::new(
+                    TokenKind::Comment { lexeme: "// note" },
+                    Span::new(2, 9, 1, 3)
+                ),
+                Token::new(
+                    TokenKind::Whitespace { lexeme: "\n\t" },
+                    Span::new(9, 11, 1, 10)
+                ),
+                Token::new(
+                    TokenKind::Comment { lexeme: "/* b */" },
+                    Span::new(11, 18, 2, 2)
+                ),
+                Token::new(
+                    TokenKind::Semicolon { lexeme: ";" },
+                    Span::new(18, 19, 2, 9)
+                ),
+                Token::new(TokenKind::Eof, Span::new(19, 19, 2, 10)),
             ]
         )
     }
+
+    #[test]
+    fn partitioning_collects_every_lexical_error() {
+        let input = "1 @2\n# \"unterminated";
+        let (tokens, errors): (Vec<_>, Vec<_>) = Lexer::new(input).partition_result();
+
+        assert_eq!(
+            tokens,
+            vec![
+                Token::new(TokenKind::Number { lexeme: 1.0 }, Span::new(0, 1, 1, 1)),
+                Token::new(TokenKind::Number { lexeme: 2.0 }, Span::new(3, 4, 1, 4)),
+                Token::new(TokenKind::Eof, Span::new(20, 20, 2, 16)),
+            ]
+        );
+        assert_eq!(
+            errors,
+            vec![
+                LexicalError::UnexpectedCharacter {
+                    char: '@',
+                    span: Span::new(2, 3, 1, 3)
+                },
+                LexicalError::UnexpectedCharacter {
+                    char: '#',
+                    span: Span::new(5, 6, 2, 1)
+                },
+                LexicalError::UnterminatedString {
+                    span: Span::new(7, 20, 2, 3)
+                },
+            ]
+        )
+    }
+
+    #[test]
+    fn scanning_non_ascii_strings_uses_byte_offsets() {
+        let input = "\"héllo\" \"日本\"\n¿";
+        let tokens = Lexer::new(input);
+
+        assert_eq!(
+            tokens.collect_vec(),
+            vec![
+                Ok(Token::new(
+                    TokenKind::String {
+                        lexeme: "\"héllo\""
+                    },
+                    Span::new(0, 8, 1, 1)
+                )),
+                Ok(Token::new(
+                    TokenKind::String {
+                        lexeme: "\"日本\""
+                    },
+                    Span::new(9, 17, 1, 9)
+                )),
+                Err(LexicalError::UnexpectedCharacter {
+                    char: '¿',
+                    span: Span::new(18, 20, 2, 1)
+                }),
+                Ok(Token::new(TokenKind::Eof, Span::new(20, 20, 2, 2))),
+            ]
+        )
+    }
+
+    #[test]
+    fn scanning_non_ascii_comments_works() {
+        let input = "// ¿qué? ☃\n¿ (";
+        let tokens = Lexer::new(input);
+
+        assert_eq!(
+            tokens.flatten().collect_vec(),
+            vec![
+                Token::new(
+                    TokenKind::LeftParen { lexeme: "(" },
+                    Span::new(18, 19, 2, 3)
+                ),
+                Token::new(TokenKind::Eof, Span::new(19, 19, 2, 4)),
+            ]
+        )
+    }
+
+    #[cfg(feature = "unicode-identifiers")]
+    #[test]
+    fn scanning_unicode_identifiers_works() {
+        let input = "café λ_1 _日本";
+        let tokens = Lexer::new(input);
+
+        assert_eq!(
+            tokens.collect_vec(),
+            vec![
+                Ok(Token::new(
+                    TokenKind::Identifier { lexeme: "café" },
+                    Span::new(0, 5, 1, 1)
+                )),
+                Ok(Token::new(
+                    TokenKind::Identifier { lexeme: "λ_1" },
+                    Span::new(6, 10, 1, 6)
+                )),
+                Ok(Token::new(
+                    TokenKind::Identifier { lexeme: "_日本" },
+                    Span::new(11, 18, 1, 10)
+                )),
+                Ok(Token::new(TokenKind::Eof, Span::new(18, 18, 1, 13))),
+            ]
+        )
+    }
+
+    #[test]
+    fn scanning_strings_decodes_escape_sequences() {
+        let input = r#""tab\tquote\"slash\\line\n\u{1F600}""#;
+        let tokens = Lexer::new(input).flatten().collect_vec();
+
+        assert_eq!(
+            tokens,
+            vec![
+                Token::new(TokenKind::String { lexeme: input }, Span::new(0, 36, 1, 1)),
+                Token::new(TokenKind::Eof, Span::new(36, 36, 1, 37)),
+            ]
+        );
+        assert_eq!(string_value(input), "tab\tquote\"slash\\line\n\u{1F600}");
+    }
+
+    #[test]
+    fn scanning_invalid_escape_sequences_produces_errors() {
+        let input = "\"fine\"\n\"bad \\q\" \"\\u{110000}\" \"\\u{41\"";
+        let errors = Lexer::new(input).filter_map(Result::err).collect_vec();
+
+        assert_eq!(
+            errors,
+            vec![
+                LexicalError::InvalidEscape {
+                    sequence: "\\q".to_string(),
+                    span: Span::new(12, 14, 2, 6)
+                },
+                LexicalError::InvalidEscape {
+                    sequence: "\\u{110000}".to_string(),
+                    span: Span::new(17, 27, 2, 11)
+                },
+                LexicalError::InvalidEscape {
+                    sequence: "\\u{41".to_string(),
+                    span: Span::new(30, 35, 2, 24)
+                },
+            ]
+        )
+    }
+
+    #[test]
+    fn scanning_raw_strings_keeps_their_contents_verbatim() {
+        let input = "r\"C:\\new\" r#\"say \"hi\"\n\"#";
+        let tokens = Lexer::new(input).flatten().collect_vec();
+
+        assert_eq!(
+            tokens,
+            vec![
+                Token::new(
+                    TokenKind::String {
+                        lexeme: "r\"C:\\new\""
+                    },
+                    Span::new(0, 9, 1, 1)
+                ),
+                Token::new(
+                    TokenKind::String {
+                        lexeme: "r#\"say \"hi\"\n\"#"
+                    },
+                    Span::new(10, 24, 1, 11)
+                ),
+                Token::new(TokenKind::Eof, Span::new(24, 24, 2, 3)),
+            ]
+        );
+        assert_eq!(string_value("r\"C:\\new\""), "C:\\new");
+        assert_eq!(string_value("r#\"say \"hi\"\n\"#"), "say \"hi\"\n");
+    }
+
+    /// The span of a token or an error.
+    fn span(result: &LexResult) -> Span {
+        match result {
+            Ok(token) => token.span(),
+            Err(error) => error.span(),
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn lexing_arbitrary_input_yields_ordered_spans_on_char_boundaries(input in any::<String>()) {
+            let results = Lexer::new(&input).collect_vec();
+
+            let mut previous_end = 0;
+            for result in &results {
+                let span = span(result);
+                prop_assert!(previous_end <= span.start && span.start <= span.end);
+                prop_assert!(input.get(span.start..span.end).is_some());
+                prop_assert_eq!(span.line, input[..span.start].matches('\n').count() + 1);
+                prop_assert_eq!(span.column, span_between(&input, span.start, span.end).column);
+                previous_end = span.end;
+            }
+            prop_assert!(matches!(results.last(), Some(Ok(token)) if token.kind() == TokenKind::Eof));
+        }
+
+        #[test]
+        fn lexing_any_string_literal_keeps_its_contents(contents in "[^\"\\\\]*") {
+            let input = format!("\"{contents}\"");
+            let tokens = Lexer::new(&input).collect_vec();
+
+            prop_assert_eq!(
+                tokens,
+                vec![
+                    Ok(Token::new(TokenKind::String { lexeme: &input }, Span::new(0, input.len(), 1, 1))),
+                    Ok(Token::new(
+                        TokenKind::Eof,
+                        Span::new(
+                            input.len(),
+                            input.len(),
+                            contents.matches('\n').count() + 1,
+                            input.rsplit('\n').next().unwrap().chars().count() + 1,
+                        ),
+                    )),
+                ]
+            );
+        }
+
+        #[test]
+        fn escaping_any_string_round_trips_through_its_literal(value in any::<String>()) {
+            let input = format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""));
+            let tokens = Lexer::new(&input).collect_vec();
+
+            let expected = TokenKind::String { lexeme: &input };
+            prop_assert!(matches!(tokens[0], Ok(token) if token.kind() == expected));
+            prop_assert_eq!(string_value(&input), value);
+        }
+
+        #[test]
+        fn lexing_any_comment_discards_it(comment in "[^\n]*") {
+            let input = format!("//{comment}");
+            let tokens = Lexer::new(&input).collect_vec();
+
+            prop_assert_eq!(tokens.len(), 1);
+            prop_assert!(matches!(tokens[0], Ok(token) if token.kind() == TokenKind::Eof));
+        }
+    }
 }
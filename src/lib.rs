@@ -1,11 +1,40 @@
 mod ast;
+mod class;
+mod convert;
+pub mod cst;
+mod diagnostic;
+mod dump;
+mod environment;
 pub mod error;
+mod function;
 mod interpreter;
 mod io;
 mod lexer;
+mod lox;
+mod native;
 mod parser;
+mod printer;
+mod resolver;
 mod run;
+mod source;
+mod span;
+mod stack;
 mod token;
+mod value;
+mod visitor;
 
+pub use convert::FromLox;
+pub use convert::IntoLox;
+pub use convert::IntoLoxResult;
+pub use diagnostic::Diagnostic;
+pub use dump::DumpFormat;
 pub use io::read_source_file;
+pub use lox::Lox;
+pub use native::Arity;
+pub use native::IntoNativeFunction;
+pub use run::dump_ast;
+pub use run::dump_tokens;
+pub use run::run_file;
 pub use run::run_prompt;
+pub use span::Span;
+pub use value::Value;
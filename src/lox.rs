@@ -0,0 +1,300 @@
+use std::io;
+use std::io::Write;
+use std::path::Path;
+use std::rc::Rc;
+
+use crate::convert::IntoLox;
+use crate::error::Error;
+use crate::error::RunTimeError;
+use crate::interpreter::Interpreter;
+use crate::io::read_source_file;
+use crate::native::Arity;
+use crate::native::IntoNativeFunction;
+use crate::native::NativeFunction;
+use crate::run::run;
+use crate::source::Source;
+use crate::value::Value;
+
+/// An interpreter for embedding Lox in Rust programs.
+///
+/// Globals defined by one call to [`Lox::eval`] or [`Lox::run_file`] stay visible to the
+/// following ones, and can be read and set from Rust with [`Lox::get_global`] and
+/// [`Lox::set_global`].
+pub struct Lox {
+    interpreter: Interpreter,
+}
+
+impl Lox {
+    /// Creates an engine that writes the output of `print` statements to stdout.
+    pub fn new() -> Self {
+        Self::with_output(io::stdout())
+    }
+
+    /// Creates an engine that writes the output of `print` statements to `output`.
+    pub fn with_output(output: impl Write + 'static) -> Self {
+        Self {
+            interpreter: Interpreter::with_output(output),
+        }
+    }
+
+    /// Runs the Lox program `source`.
+    pub fn eval(
+        &mut self,
+        source: &str,
+    ) -> Result<(), Error> {
+        self.run("<eval>", source)
+    }
+
+    /// Runs the Lox program in the file at `path`.
+    pub fn run_file(
+        &mut self,
+        path: &Path,
+    ) -> Result<(), Error> {
+        let source = read_source_file(path)?;
+        self.run(path.display().to_string(), &source)
+    }
+
+    /// Runs `source`, reporting errors in it as coming from `file_name`.
+    pub(crate) fn run(
+        &mut self,
+        file_name: impl Into<String>,
+        source: &str,
+    ) -> Result<(), Error> {
+        run(
+            &Rc::new(Source::new(file_name, source)),
+            &mut self.interpreter,
+        )
+    }
+
+    /// The value of the global variable `name`, if it is defined.
+    pub fn get_global(
+        &self,
+        name: &str,
+    ) -> Option<Value> {
+        self.interpreter.get_global(name)
+    }
+
+    /// Defines the global variable `name`, or assigns `value` to it if it is already defined.
+    pub fn set_global(
+        &mut self,
+        name: &str,
+        value: impl IntoLox,
+    ) {
+        self.interpreter.define_global(name, value.into_lox());
+    }
+
+    /// Defines the global function `name`, implemented by `function`. Lox programs calling it with
+    /// a number of arguments not matching `arity` fail before `function` is called.
+    pub fn define_native(
+        &mut self,
+        name: &str,
+        arity: Arity,
+        function: impl Fn(&[Value]) -> Result<Value, RunTimeError> + 'static,
+    ) {
+        let function = NativeFunction::new(name, arity, function);
+        self.set_global(name, Value::NativeFunction(Rc::new(function)));
+    }
+
+    /// Defines the global function `name`, implemented by a closure with typed parameters like
+    /// `|a: f64, b: f64| a + b`.
+    pub fn define_function<Params>(
+        &mut self,
+        name: &str,
+        function: impl IntoNativeFunction<Params>,
+    ) {
+        let function = function.into_native_function(name);
+        self.set_global(name, Value::NativeFunction(Rc::new(function)));
+    }
+}
+
+impl Drop for Lox {
+    fn drop(&mut self) {
+        self.interpreter.clear_globals();
+    }
+}
+
+impl Default for Lox {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::convert::FromLox;
+
+    fn engine() -> Lox {
+        Lox::with_output(io::sink())
+    }
+
+    #[test]
+    fn globals_persist_between_calls() {
+        let mut lox = engine();
+        lox.eval("var a = 1; fun inc() { a = a + 1; }").unwrap();
+        lox.eval("inc(); inc();").unwrap();
+
+        assert!(matches!(lox.get_global("a"), Some(Value::Number(n)) if n == 3.0));
+        assert!(lox.get_global("b").is_none());
+    }
+
+    #[test]
+    fn functions_outlive_the_source_they_were_declared_in() {
+        let mut lox = engine();
+        let source = String::from("class A { get() { return 1; } } fun f() { return A().get(); }");
+        lox.eval(&source).unwrap();
+        drop(source);
+        lox.eval("var a = f();").unwrap();
+
+        assert!(matches!(lox.get_global("a"), Some(Value::Number(n)) if n == 1.0));
+    }
+
+    #[test]
+    fn dropping_the_engine_frees_its_globals() {
+        let marker = Rc::new(());
+        let held = Rc::clone(&marker);
+        let mut lox = engine();
+        lox.define_native("held", Arity::Fixed(0), move |_| {
+            let _ = &held;
+            Ok(Value::None)
+        });
+        lox.eval("fun f() { return held(); }\nclass A { m() {} }\nvar a = A();")
+            .unwrap();
+        drop(lox);
+
+        assert_eq!(Rc::strong_count(&marker), 1);
+    }
+
+    #[test]
+    fn globals_set_from_rust_are_visible_to_lox() {
+        let mut lox = engine();
+        lox.set_global("x", 2.0);
+        lox.eval("var y = x * 3;").unwrap();
+        lox.set_global("x", true);
+        lox.eval("if (x) y = y + 1;").unwrap();
+
+        assert!(matches!(lox.get_global("y"), Some(Value::Number(n)) if n == 7.0));
+    }
+
+    #[test]
+    fn errors_leave_earlier_globals_intact() {
+        let mut lox = engine();
+        lox.eval("var a = \"kept\";").unwrap();
+        let error = lox.eval("a = -nil;").unwrap_err();
+
+        assert_eq!(error.exit_code(), 70);
+        assert!(error.report().contains("<eval>"));
+        assert!(matches!(lox.get_global("a"), Some(Value::String(s)) if &*s == "kept"));
+    }
+
+    #[test]
+    fn errors_in_functions_point_into_the_source_declaring_them() {
+        let mut lox = engine();
+        lox.eval("fun f() {\n  return -\"x\";\n}").unwrap();
+        let error = lox.run("<second>", "f();").unwrap_err();
+
+        let report = error.report();
+        assert!(report.contains("<eval>:2:10"), "{report}");
+        assert!(report.contains("return -\"x\";"), "{report}");
+        assert!(!report.contains("<second>"), "{report}");
+    }
+
+    #[test]
+    fn native_functions_can_be_called_from_lox() {
+        let mut lox = engine();
+        lox.define_native("sum", Arity::Variadic, |arguments| {
+            arguments
+                .iter()
+                .try_fold(Value::Number(0.0), |sum, argument| match (sum, argument) {
+                    (Value::Number(sum), Value::Number(n)) => Ok(Value::Number(sum + n)),
+                    (_, argument) => Err(RunTimeError::native(format!("cannot add {argument}"))),
+                })
+        });
+        lox.define_native("twice", Arity::Fixed(1), |arguments| {
+            Ok(Value::Number(
+                2.0 * f64::from(matches!(arguments[0], Value::Boolean(true))),
+            ))
+        });
+        lox.eval("var a = sum(1, 2, 3) + sum() + twice(true);")
+            .unwrap();
+
+        assert!(matches!(lox.get_global("a"), Some(Value::Number(n)) if n == 8.0));
+    }
+
+    #[test]
+    fn native_function_errors_point_at_the_call() {
+        let mut lox = engine();
+        lox.define_native("fail", Arity::Fixed(0), |_| {
+            Err(RunTimeError::native("failed"))
+        });
+
+        let error = lox
+            .eval(
+                "var a = 1;
+fail();",
+            )
+            .unwrap_err();
+        let [diagnostic] = error.diagnostics().try_into().unwrap();
+        assert_eq!(diagnostic.span().line, 2);
+        assert!(matches!(
+            lox.eval("fail(1);").unwrap_err().diagnostics()[..],
+            [ref diagnostic] if diagnostic.message().contains("expected 0 arguments")
+        ));
+    }
+
+    #[test]
+    fn typed_functions_convert_their_arguments_and_result() {
+        let mut lox = engine();
+        lox.define_function("hypot", |a: f64, b: f64| a.hypot(b));
+        lox.define_function("shout", |s: String, times: Option<f64>| {
+            s.to_uppercase().repeat(times.unwrap_or(1.0) as usize)
+        });
+        lox.define_function("names", || vec!["a", "b"]);
+        lox.define_function("check", |ok: bool| {
+            if ok {
+                Ok(())
+            } else {
+                Err(RunTimeError::native("check failed"))
+            }
+        });
+        lox.eval(
+            "var a = hypot(3, 4);\n\
+             var b = shout(\"hi\", 2);\n\
+             var c = shout(\"x\", nil);\n\
+             var d = names();\n\
+             check(true);",
+        )
+        .unwrap();
+
+        assert_eq!(f64::from_lox(lox.get_global("a").unwrap()).unwrap(), 5.0);
+        assert_eq!(
+            String::from_lox(lox.get_global("b").unwrap()).unwrap(),
+            "HIHI"
+        );
+        assert_eq!(String::from_lox(lox.get_global("c").unwrap()).unwrap(), "X");
+        assert_eq!(
+            Vec::<String>::from_lox(lox.get_global("d").unwrap()).unwrap(),
+            ["a", "b"]
+        );
+        assert!(lox.eval("check(false);").is_err());
+    }
+
+    #[test]
+    fn typed_functions_report_mismatched_arguments() {
+        let mut lox = engine();
+        lox.define_function("negate", |n: f64| -n);
+
+        let error = lox.eval("print negate(\"one\");").unwrap_err();
+        let [diagnostic] = error.diagnostics().try_into().unwrap();
+        assert_eq!(diagnostic.message(), "expected number but found string");
+        assert_eq!(diagnostic.span().column, 7);
+    }
+
+    #[test]
+    fn clock_is_built_in() {
+        let mut lox = engine();
+        lox.eval("var start = clock();").unwrap();
+
+        assert!(matches!(lox.get_global("start"), Some(Value::Number(n)) if n > 0.0));
+    }
+}
@@ -1,28 +1,88 @@
+use std::io;
+use std::io::Write;
 use std::path::PathBuf;
+use std::process::ExitCode;
 
+use clap::ArgGroup;
 use clap::Parser;
+use clap::ValueEnum;
+use rlox::dump_ast;
+use rlox::dump_tokens;
+use rlox::error::Error;
 use rlox::error::PublicError;
-use rlox::read_source_file;
+use rlox::run_file;
 use rlox::run_prompt;
+use rlox::DumpFormat;
 
 #[derive(Parser, Debug)]
 #[command(version, about, long_about = None)]
+#[command(group(ArgGroup::new("dump").args(["dump_tokens", "dump_ast"])))]
 struct Args {
     // The path of a rlox source file to interpret
     #[arg(short, long)]
     file: Option<PathBuf>,
+
+    // Print the tokens of the file instead of interpreting it
+    #[arg(long, requires = "file")]
+    dump_tokens: bool,
+
+    // Print the syntax tree of the file instead of interpreting it
+    #[arg(long, requires = "file")]
+    dump_ast: bool,
+
+    // The output format of --dump-tokens and --dump-ast
+    #[arg(long, value_enum, default_value_t = Format::Human, requires = "dump")]
+    format: Format,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum Format {
+    Human,
+    Json,
+}
+
+impl From<Format> for DumpFormat {
+    fn from(format: Format) -> Self {
+        match format {
+            Format::Human => DumpFormat::Human,
+            Format::Json => DumpFormat::Json,
+        }
+    }
 }
 
-fn main() -> Result<(), PublicError> {
+fn main() -> ExitCode {
     let args = Args::parse();
 
+    match run(args) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("{}", e.report());
+            ExitCode::from(e.exit_code())
+        }
+    }
+}
+
+fn run(args: Args) -> Result<(), PublicError> {
     if let Some(file_path) = args.file {
-        // TODO put this behind struct
-        let _raw_source = read_source_file(&file_path)?;
+        if args.dump_tokens {
+            print(&dump_tokens(&file_path, args.format.into())?)?;
+        } else if args.dump_ast {
+            print(&dump_ast(&file_path, args.format.into())?)?;
+        } else {
+            run_file(&file_path)?;
+        }
     } else {
-        // TODO put this behind struct
         run_prompt()?;
     }
-    println!("Hello, world!");
     Ok(())
 }
+
+/// Writes `output` to stdout. Stopping early when the reader closes the pipe, like `head` does,
+/// is not an error.
+fn print(output: &str) -> Result<(), PublicError> {
+    let mut stdout = io::stdout().lock();
+    match writeln!(stdout, "{output}").and_then(|()| stdout.flush()) {
+        Err(error) if error.kind() != io::ErrorKind::BrokenPipe => Err(Error::from(error).into()),
+        _ => Ok(()),
+    }
+}
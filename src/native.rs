@@ -0,0 +1,130 @@
+use std::fmt::Debug;
+use std::fmt::Display;
+use std::fmt::Formatter;
+use std::time::SystemTime;
+use std::time::UNIX_EPOCH;
+
+use crate::convert::FromLox;
+use crate::convert::IntoLoxResult;
+use crate::error::RunTimeError;
+use crate::span::Span;
+use crate::value::Value;
+
+/// The number of arguments a native function accepts.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Arity {
+    /// Exactly this many arguments.
+    Fixed(usize),
+    /// Any number of arguments.
+    Variadic,
+}
+
+type Callable = dyn Fn(&[Value]) -> Result<Value, RunTimeError>;
+
+/// A function implemented in Rust that Lox programs can call like any other function.
+pub struct NativeFunction {
+    name: String,
+    arity: Arity,
+    function: Box<Callable>,
+}
+
+impl NativeFunction {
+    pub(crate) fn new(
+        name: impl Into<String>,
+        arity: Arity,
+        function: impl Fn(&[Value]) -> Result<Value, RunTimeError> + 'static,
+    ) -> Self {
+        Self {
+            name: name.into(),
+            arity,
+            function: Box::new(function),
+        }
+    }
+
+    pub(crate) fn arity(&self) -> Arity {
+        self.arity
+    }
+
+    /// Calls the function, pointing errors it reports with [`RunTimeError::native`] or
+    /// [`RunTimeError::type_mismatch`] at `span`.
+    pub(crate) fn call(
+        &self,
+        arguments: &[Value],
+        span: Span,
+    ) -> Result<Value, RunTimeError> {
+        (self.function)(arguments).map_err(|error| error.at(span))
+    }
+}
+
+impl Display for NativeFunction {
+    fn fmt(
+        &self,
+        f: &mut Formatter<'_>,
+    ) -> std::fmt::Result {
+        write!(f, "<native fn {}>", self.name)
+    }
+}
+
+impl Debug for NativeFunction {
+    fn fmt(
+        &self,
+        f: &mut Formatter<'_>,
+    ) -> std::fmt::Result {
+        f.debug_struct("NativeFunction")
+            .field("name", &self.name)
+            .field("arity", &self.arity)
+            .finish_non_exhaustive()
+    }
+}
+
+/// A Rust function with typed parameters that can be called from Lox. Arguments are converted
+/// with [`FromLox`] and the result with [`IntoLoxResult`], so that arguments of the wrong type
+/// fail with a [`RunTimeError::TypeMismatch`] pointed at the call.
+///
+/// Implemented for closures taking up to four parameters; `Params` is the tuple of their types.
+pub trait IntoNativeFunction<Params> {
+    fn into_native_function(
+        self,
+        name: &str,
+    ) -> NativeFunction;
+}
+
+macro_rules! impl_into_native_function {
+    ($($param:ident $argument:ident),*) => {
+        impl<F, R, $($param),*> IntoNativeFunction<($($param,)*)> for F
+        where
+            F: Fn($($param),*) -> R + 'static,
+            R: IntoLoxResult,
+            $($param: FromLox,)*
+        {
+            fn into_native_function(
+                self,
+                name: &str,
+            ) -> NativeFunction {
+                let params: &[&str] = &[$(stringify!($param)),*];
+                NativeFunction::new(name, Arity::Fixed(params.len()), move |arguments| {
+                    let [$($argument),*] = arguments else {
+                        unreachable!("arity is checked before native functions are called");
+                    };
+                    self($($param::from_lox($argument.clone())?),*).into_lox_result()
+                })
+            }
+        }
+    };
+}
+
+impl_into_native_function!();
+impl_into_native_function!(A a);
+impl_into_native_function!(A a, B b);
+impl_into_native_function!(A a, B b, C c);
+impl_into_native_function!(A a, B b, C c, D d);
+
+/// `clock()`, the number of seconds since the Unix epoch, for timing programs.
+pub(crate) fn clock() -> NativeFunction {
+    NativeFunction::new("clock", Arity::Fixed(0), |_| {
+        let elapsed = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_err(|_| RunTimeError::native("the system clock is set before the Unix epoch"))?;
+        Ok(Value::Number(elapsed.as_secs_f64()))
+    })
+}
@@ -1,45 +1,594 @@
-use itertools::peek_nth;
-use itertools::PeekNth;
+use std::iter::Peekable;
+use std::rc::Rc;
 
+use crate::ast::ClassDecl;
+use crate::ast::Depth;
 use crate::ast::Expr;
+use crate::ast::FunctionDecl;
+use crate::ast::Stmt;
 use crate::error::ParseError;
-use crate::error::ParseErrorInternal;
+use crate::lexer::string_value;
+use crate::span::Span;
+use crate::stack;
 use crate::token::Token;
 use crate::token::TokenKind;
 
-pub(crate) struct Parser<'a> {
-    tokens: PeekNth<std::vec::IntoIter<Token<'a>>>,
+/// The maximum number of parameters of a function and arguments of a call.
+const MAX_ARGUMENTS: usize = 255;
+
+/// The maximum depth of nested statements, function declarations and expressions.
+const MAX_NESTING: usize = 256;
+
+pub(crate) struct Parser<'a, I>
+where
+    I: Iterator<Item = Token<'a>>,
+{
+    tokens: TokenStream<'a, I>,
+    /// The errors of every declaration that failed to parse so far.
+    errors: Vec<ParseError>,
+    /// How deeply the statement or expression being parsed is nested.
+    depth: usize,
 }
 
-impl<'a> Parser<'a> {
-    pub(crate) fn new<I>(tokens: I) -> Self
+impl<'a, I> Parser<'a, I>
+where
+    I: Iterator<Item = Token<'a>>,
+{
+    /// Creates a parser reading tokens from any source, such as a [`Lexer`](crate::lexer::Lexer)
+    /// whose errors were filtered out or a `Vec` of tokens lexed up front.
+    pub(crate) fn new<T>(tokens: T) -> Self
     where
-        I: IntoIterator<Item = Token<'a>, IntoIter = std::vec::IntoIter<Token<'a>>>,
+        T: IntoIterator<Item = Token<'a>, IntoIter = I>,
     {
         Self {
-            tokens: peek_nth(tokens),
+            tokens: TokenStream {
+                tokens: tokens.into_iter().peekable(),
+                end: Span::default(),
+            },
+            errors: vec![],
+            depth: 0,
+        }
+    }
+
+    /// Parses the whole program. Parsing recovers from syntax errors at statement boundaries, so
+    /// every syntax error is reported rather than just the first one.
+    pub(crate) fn parse(&mut self) -> Result<Vec<Stmt>, Vec<ParseError>> {
+        let mut statements = vec![];
+        while !self.is_at_end() {
+            if let Some(statement) = self.declaration() {
+                statements.push(statement);
+            }
+        }
+        if self.errors.is_empty() {
+            Ok(statements)
+        } else {
+            Err(std::mem::take(&mut self.errors))
+        }
+    }
+
+    /// Parses a declaration, recording its error and skipping to the start of the next statement
+    /// if it is invalid.
+    fn declaration(&mut self) -> Option<Stmt> {
+        match self.declaration_inner() {
+            Ok(statement) => Some(statement),
+            Err(error) => {
+                self.errors.push(error);
+                self.synchronize();
+                None
+            }
+        }
+    }
+
+    /// Discards tokens until the end of the current statement, either after a `;` or before a
+    /// keyword that starts a new statement.
+    fn synchronize(&mut self) {
+        while let Some(token) = self
+            .tokens
+            .next_if(|token| !matches!(token.kind(), TokenKind::Eof))
+        {
+            if matches!(token.kind(), TokenKind::Semicolon { .. }) {
+                return;
+            }
+            if self.check(|kind| {
+                matches!(
+                    kind,
+                    TokenKind::Class { .. }
+                        | TokenKind::Fun { .. }
+                        | TokenKind::Var { .. }
+                        | TokenKind::For { .. }
+                        | TokenKind::If { .. }
+                        | TokenKind::While { .. }
+                        | TokenKind::Print { .. }
+                        | TokenKind::Return { .. }
+                )
+            }) {
+                return;
+            }
+        }
+    }
+
+    fn declaration_inner(&mut self) -> Result<Stmt, ParseError> {
+        if let Some(token) = self.tokens.peek() {
+            match token.kind() {
+                TokenKind::Var { .. } => {
+                    let keyword = self.tokens.next().expect("cannot fail");
+                    return self.var_declaration(keyword);
+                }
+                TokenKind::Class { .. } => {
+                    let keyword = self.tokens.next().expect("cannot fail");
+                    return self.class_declaration(keyword);
+                }
+                TokenKind::Fun { .. } => {
+                    let keyword = self.tokens.next().expect("cannot fail");
+                    let mut function = self.function()?;
+                    function.span = keyword.span().merge(function.span);
+                    return Ok(Stmt::Function(Rc::new(function)));
+                }
+                _ => (),
+            }
+        }
+        self.statement()
+    }
+
+    fn class_declaration(
+        &mut self,
+        keyword: Token<'a>,
+    ) -> Result<Stmt, ParseError> {
+        let name = self.consume(
+            |kind| matches!(kind, TokenKind::Identifier { .. }),
+            |span| ParseError::ExpectedClassName { span },
+        )?;
+        let superclass = if self
+            .next_if_kind(|kind| matches!(kind, TokenKind::Less { .. }))
+            .is_some()
+        {
+            let superclass_name = self.consume(
+                |kind| matches!(kind, TokenKind::Identifier { .. }),
+                |span| ParseError::ExpectedSuperclassName { span },
+            )?;
+            if superclass_name.identifier() == name.identifier() {
+                return Err(ParseError::ClassInheritsFromItself {
+                    span: superclass_name.span(),
+                    class_name: name.span(),
+                });
+            }
+            Some(Expr::Variable {
+                name: superclass_name.into(),
+                depth: Depth::default(),
+            })
+        } else {
+            None
+        };
+        self.consume(
+            |kind| matches!(kind, TokenKind::LeftBrace { .. }),
+            |span| ParseError::ExpectedOpeningBraceBeforeClassBody { span },
+        )?;
+        let mut methods = vec![];
+        while !self.is_at_end() && !self.check(|kind| matches!(kind, TokenKind::RightBrace { .. }))
+        {
+            methods.push(Rc::new(self.function()?));
+        }
+        let closing_brace = self.consume(
+            |kind| matches!(kind, TokenKind::RightBrace { .. }),
+            |span| ParseError::ExpectedClosingBraceAfterClassBody { span },
+        )?;
+        Ok(Stmt::Class(ClassDecl {
+            name: name.into(),
+            superclass,
+            methods,
+            span: keyword.span().merge(closing_brace.span()),
+        }))
+    }
+
+    /// Parses a function or method starting at its name. The span of the declaration starts at the
+    /// name as well.
+    fn function(&mut self) -> Result<FunctionDecl, ParseError> {
+        self.nested(Self::function_inner)
+    }
+
+    fn function_inner(&mut self) -> Result<FunctionDecl, ParseError> {
+        let name = self.consume(
+            |kind| matches!(kind, TokenKind::Identifier { .. }),
+            |span| ParseError::ExpectedFunctionName { span },
+        )?;
+        self.consume(
+            |kind| matches!(kind, TokenKind::LeftParen { .. }),
+            |span| ParseError::ExpectedOpeningParenAfterFunctionName { span },
+        )?;
+        let mut params = vec![];
+        if !self.check(|kind| matches!(kind, TokenKind::RightParen { .. })) {
+            loop {
+                let param = self.consume(
+                    |kind| matches!(kind, TokenKind::Identifier { .. }),
+                    |span| ParseError::ExpectedParameterName { span },
+                )?;
+                if params.len() >= MAX_ARGUMENTS {
+                    return Err(ParseError::TooManyParameters {
+                        max: MAX_ARGUMENTS,
+                        span: param.span(),
+                    });
+                }
+                params.push(param.into());
+                if self
+                    .next_if_kind(|kind| matches!(kind, TokenKind::Comma { .. }))
+                    .is_none()
+                {
+                    break;
+                }
+            }
+        }
+        self.consume(
+            |kind| matches!(kind, TokenKind::RightParen { .. }),
+            |span| ParseError::ExpectedClosingParenAfterParameters { span },
+        )?;
+        let opening_brace = self.consume(
+            |kind| matches!(kind, TokenKind::LeftBrace { .. }),
+            |span| ParseError::ExpectedOpeningBraceBeforeFunctionBody { span },
+        )?;
+        let (body, closing_brace) = self.block(opening_brace)?;
+        Ok(FunctionDecl {
+            name: name.into(),
+            params,
+            body,
+            span: name.span().merge(closing_brace.span()),
+        })
+    }
+
+    fn var_declaration(
+        &mut self,
+        keyword: Token<'a>,
+    ) -> Result<Stmt, ParseError> {
+        let name = self.consume(
+            |kind| matches!(kind, TokenKind::Identifier { .. }),
+            |span| ParseError::ExpectedVariableName { span },
+        )?;
+        let initializer = if self
+            .next_if_kind(|kind| matches!(kind, TokenKind::Equal { .. }))
+            .is_some()
+        {
+            Some(self.expression()?)
+        } else {
+            None
+        };
+        let semicolon = self.consume(
+            |kind| matches!(kind, TokenKind::Semicolon { .. }),
+            |span| ParseError::ExpectedSemicolonAfterVarDecl { span },
+        )?;
+        Ok(Stmt::Var {
+            name: name.into(),
+            initializer,
+            span: keyword.span().merge(semicolon.span()),
+        })
+    }
+
+    fn statement(&mut self) -> Result<Stmt, ParseError> {
+        self.nested(Self::statement_inner)
+    }
+
+    fn statement_inner(&mut self) -> Result<Stmt, ParseError> {
+        if let Some(token) = self.tokens.peek() {
+            match token.kind() {
+                TokenKind::Print { .. } => {
+                    let keyword = self.tokens.next().expect("cannot fail");
+                    return self.print_statement(keyword);
+                }
+                TokenKind::LeftBrace { .. } => {
+                    let opening_brace = self.tokens.next().expect("cannot fail");
+                    let (statements, closing_brace) = self.block(opening_brace)?;
+                    return Ok(Stmt::Block {
+                        statements,
+                        span: opening_brace.span().merge(closing_brace.span()),
+                    });
+                }
+                TokenKind::If { .. } => {
+                    let keyword = self.tokens.next().expect("cannot fail");
+                    return self.if_statement(keyword);
+                }
+                TokenKind::While { .. } => {
+                    let keyword = self.tokens.next().expect("cannot fail");
+                    return self.while_statement(keyword);
+                }
+                TokenKind::For { .. } => {
+                    let keyword = self.tokens.next().expect("cannot fail");
+                    return self.for_statement(keyword);
+                }
+                TokenKind::Return { .. } => {
+                    let keyword = self.tokens.next().expect("cannot fail");
+                    return self.return_statement(keyword);
+                }
+                _ => (),
+            }
+        }
+        self.expression_statement()
+    }
+
+    /// Parses the statements of a block whose opening brace has already been consumed, returning
+    /// them along with the closing brace.
+    fn block(
+        &mut self,
+        opening_brace: Token<'a>,
+    ) -> Result<(Vec<Stmt>, Token<'a>), ParseError> {
+        let mut statements = vec![];
+        while !self.is_at_end() && !self.check(|kind| matches!(kind, TokenKind::RightBrace { .. }))
+        {
+            if let Some(statement) = self.declaration() {
+                statements.push(statement);
+            }
+        }
+        let closing_brace = self.consume(
+            |kind| matches!(kind, TokenKind::RightBrace { .. }),
+            |span| ParseError::ExpectedClosingBraceAfterBlock {
+                span,
+                opening: opening_brace.span(),
+            },
+        )?;
+        Ok((statements, closing_brace))
+    }
+
+    fn if_statement(
+        &mut self,
+        keyword: Token<'a>,
+    ) -> Result<Stmt, ParseError> {
+        let condition = self.parenthesized_condition("if")?;
+        let then_branch = Box::new(self.statement()?);
+        let else_branch = if self
+            .next_if_kind(|kind| matches!(kind, TokenKind::Else { .. }))
+            .is_some()
+        {
+            Some(Box::new(self.statement()?))
+        } else {
+            None
+        };
+        let end = else_branch.as_ref().unwrap_or(&then_branch).span();
+        Ok(Stmt::If {
+            condition,
+            then_branch,
+            else_branch,
+            span: keyword.span().merge(end),
+        })
+    }
+
+    fn return_statement(
+        &mut self,
+        keyword: Token<'a>,
+    ) -> Result<Stmt, ParseError> {
+        let value = if self.check(|kind| matches!(kind, TokenKind::Semicolon { .. })) {
+            None
+        } else {
+            Some(self.expression()?)
+        };
+        let semicolon = self.consume(
+            |kind| matches!(kind, TokenKind::Semicolon { .. }),
+            |span| ParseError::ExpectedSemicolonAfterReturnValue { span },
+        )?;
+        Ok(Stmt::Return {
+            keyword: keyword.span(),
+            value,
+            span: keyword.span().merge(semicolon.span()),
+        })
+    }
+
+    fn while_statement(
+        &mut self,
+        keyword: Token<'a>,
+    ) -> Result<Stmt, ParseError> {
+        let condition = self.parenthesized_condition("while")?;
+        let body = Box::new(self.statement()?);
+        let span = keyword.span().merge(body.span());
+        Ok(Stmt::While {
+            condition,
+            body,
+            span,
+        })
+    }
+
+    /// Parses a `for` loop and desugars it into an equivalent `while` loop. Every statement the
+    /// loop desugars into spans the whole loop.
+    fn for_statement(
+        &mut self,
+        keyword: Token<'a>,
+    ) -> Result<Stmt, ParseError> {
+        self.consume(
+            |kind| matches!(kind, TokenKind::LeftParen { .. }),
+            |span| ParseError::ExpectedOpeningParenAfterKeyword {
+                keyword: "for",
+                span,
+            },
+        )?;
+
+        let initializer = if let Some(token) = self.tokens.peek() {
+            match token.kind() {
+                TokenKind::Semicolon { .. } => {
+                    self.tokens.next();
+                    None
+                }
+                TokenKind::Var { .. } => {
+                    let keyword = self.tokens.next().expect("cannot fail");
+                    Some(self.var_declaration(keyword)?)
+                }
+                _ => Some(self.expression_statement()?),
+            }
+        } else {
+            return Err(ParseError::UnexpectedEof {
+                span: self.tokens.end,
+            });
+        };
+
+        let condition = if let Some(semicolon) = self
+            .tokens
+            .peek()
+            .filter(|token| matches!(token.kind(), TokenKind::Semicolon { .. }))
+        {
+            Expr::BooleanLiteral {
+                value: true,
+                span: semicolon.span(),
+            }
+        } else {
+            self.expression()?
+        };
+        self.consume(
+            |kind| matches!(kind, TokenKind::Semicolon { .. }),
+            |span| ParseError::ExpectedSemicolonAfterLoopCondition { span },
+        )?;
+
+        let increment = if self.check(|kind| matches!(kind, TokenKind::RightParen { .. })) {
+            None
+        } else {
+            Some(self.expression()?)
+        };
+        self.consume(
+            |kind| matches!(kind, TokenKind::RightParen { .. }),
+            |span| ParseError::ExpectedClosingParenAfterForClauses { span },
+        )?;
+
+        let mut body = self.statement()?;
+        let span = keyword.span().merge(body.span());
+        if let Some(increment) = increment {
+            body = Stmt::Block {
+                statements: vec![
+                    body,
+                    Stmt::Expression {
+                        span: increment.span(),
+                        expression: increment,
+                    },
+                ],
+                span,
+            };
+        }
+        body = Stmt::While {
+            condition,
+            body: Box::new(body),
+            span,
+        };
+        if let Some(initializer) = initializer {
+            body = Stmt::Block {
+                statements: vec![initializer, body],
+                span,
+            };
+        }
+        Ok(body)
+    }
+
+    /// Parses the parenthesized condition following the `keyword` of an `if` or `while` statement.
+    fn parenthesized_condition(
+        &mut self,
+        keyword: &'static str,
+    ) -> Result<Expr, ParseError> {
+        self.consume(
+            |kind| matches!(kind, TokenKind::LeftParen { .. }),
+            |span| ParseError::ExpectedOpeningParenAfterKeyword { keyword, span },
+        )?;
+        let condition = self.expression()?;
+        self.consume(
+            |kind| matches!(kind, TokenKind::RightParen { .. }),
+            |span| ParseError::ExpectedClosingParenAfterCondition { span },
+        )?;
+        Ok(condition)
+    }
+
+    fn print_statement(
+        &mut self,
+        keyword: Token<'a>,
+    ) -> Result<Stmt, ParseError> {
+        let expression = self.expression()?;
+        let semicolon = self.consume(
+            |kind| matches!(kind, TokenKind::Semicolon { .. }),
+            |span| ParseError::ExpectedSemicolonAfterValue { span },
+        )?;
+        Ok(Stmt::Print {
+            expression,
+            span: keyword.span().merge(semicolon.span()),
+        })
+    }
+
+    fn expression_statement(&mut self) -> Result<Stmt, ParseError> {
+        let expression = self.expression()?;
+        let semicolon = self.consume(
+            |kind| matches!(kind, TokenKind::Semicolon { .. }),
+            |span| ParseError::ExpectedSemicolonAfterExpr { span },
+        )?;
+        let span = expression.span().merge(semicolon.span());
+        Ok(Stmt::Expression { expression, span })
+    }
+
+    fn expression(&mut self) -> Result<Expr, ParseError> {
+        self.nested(Self::assignment)
+    }
+
+    fn assignment(&mut self) -> Result<Expr, ParseError> {
+        let expr = self.or()?;
+
+        if let Some(equals) = self.next_if_kind(|kind| matches!(kind, TokenKind::Equal { .. })) {
+            let value = self.nested(Self::assignment)?;
+            return match expr {
+                Expr::Variable { name, .. } => Ok(Expr::Assign {
+                    name,
+                    value: Box::new(value),
+                    depth: Depth::default(),
+                }),
+                Expr::Get { object, name } => Ok(Expr::Set {
+                    object,
+                    name,
+                    value: Box::new(value),
+                }),
+                _ => Err(ParseError::InvalidAssignmentTarget {
+                    span: equals.span(),
+                }),
+            };
         }
+
+        Ok(expr)
     }
 
-    pub(crate) fn parse(&mut self) -> Result<Expr<'a>, ParseError> {
-        self.expression()
+    fn or(&mut self) -> Result<Expr, ParseError> {
+        let mut expr = self.and()?;
+        let depth = self.depth;
+
+        while let Some(operator) = self.next_if_kind(|kind| matches!(kind, TokenKind::Or { .. })) {
+            self.deepen()?;
+            let right = self.and()?;
+            expr = Expr::Logical {
+                left: Box::new(expr),
+                operator: operator.into(),
+                right: Box::new(right),
+            };
+        }
+
+        self.depth = depth;
+        Ok(expr)
     }
 
-    fn expression(&mut self) -> Result<Expr<'a>, ParseError> {
-        self.equality()
+    fn and(&mut self) -> Result<Expr, ParseError> {
+        let mut expr = self.equality()?;
+        let depth = self.depth;
+
+        while let Some(operator) = self.next_if_kind(|kind| matches!(kind, TokenKind::And { .. })) {
+            self.deepen()?;
+            let right = self.equality()?;
+            expr = Expr::Logical {
+                left: Box::new(expr),
+                operator: operator.into(),
+                right: Box::new(right),
+            };
+        }
+
+        self.depth = depth;
+        Ok(expr)
     }
 
-    fn equality(&mut self) -> Result<Expr<'a>, ParseError> {
+    fn equality(&mut self) -> Result<Expr, ParseError> {
         let mut expr = self.comparison()?;
+        let depth = self.depth;
 
         while let Some(token) = self.tokens.peek() {
             match token.kind() {
                 TokenKind::BangEqual { .. } | TokenKind::EqualEqual { .. } => {
                     let operator = self.tokens.next().expect("cannot fail");
+                    self.deepen()?;
                     let right = self.comparison()?;
                     expr = Expr::Binary {
                         left: Box::new(expr),
-                        operator,
+                        operator: operator.into(),
                         right: Box::new(right),
                     };
                 }
@@ -47,11 +596,13 @@ impl<'a> Parser<'a> {
             }
         }
 
+        self.depth = depth;
         Ok(expr)
     }
 
-    fn comparison(&mut self) -> Result<Expr<'a>, ParseError> {
+    fn comparison(&mut self) -> Result<Expr, ParseError> {
         let mut expr = self.term()?;
+        let depth = self.depth;
 
         while let Some(token) = self.tokens.peek() {
             match token.kind() {
@@ -60,10 +611,11 @@ impl<'a> Parser<'a> {
                 | TokenKind::Less { .. }
                 | TokenKind::LessEqual { .. } => {
                     let operator = self.tokens.next().expect("cannot fail");
+                    self.deepen()?;
                     let right = self.term()?;
                     expr = Expr::Binary {
                         left: Box::new(expr),
-                        operator,
+                        operator: operator.into(),
                         right: Box::new(right),
                     };
                 }
@@ -71,20 +623,23 @@ impl<'a> Parser<'a> {
             }
         }
 
+        self.depth = depth;
         Ok(expr)
     }
 
-    fn term(&mut self) -> Result<Expr<'a>, ParseError> {
+    fn term(&mut self) -> Result<Expr, ParseError> {
         let mut expr = self.factor()?;
+        let depth = self.depth;
 
         while let Some(token) = self.tokens.peek() {
             match token.kind() {
                 TokenKind::Minus { .. } | TokenKind::Plus { .. } => {
                     let operator = self.tokens.next().expect("cannot fail");
+                    self.deepen()?;
                     let right = self.factor()?;
                     expr = Expr::Binary {
                         left: Box::new(expr),
-                        operator,
+                        operator: operator.into(),
                         right: Box::new(right),
                     };
                 }
@@ -92,20 +647,23 @@ impl<'a> Parser<'a> {
             }
         }
 
+        self.depth = depth;
         Ok(expr)
     }
 
-    fn factor(&mut self) -> Result<Expr<'a>, ParseError> {
+    fn factor(&mut self) -> Result<Expr, ParseError> {
         let mut expr = self.unary()?;
+        let depth = self.depth;
 
         while let Some(token) = self.tokens.peek() {
             match token.kind() {
                 TokenKind::Star { .. } | TokenKind::Slash { .. } => {
                     let operator = self.tokens.next().expect("cannot fail");
+                    self.deepen()?;
                     let right = self.unary()?;
                     expr = Expr::Binary {
                         left: Box::new(expr),
-                        operator,
+                        operator: operator.into(),
                         right: Box::new(right),
                     };
                 }
@@ -113,109 +671,814 @@ impl<'a> Parser<'a> {
             }
         }
 
+        self.depth = depth;
         Ok(expr)
     }
 
-    fn unary(&mut self) -> Result<Expr<'a>, ParseError> {
+    fn unary(&mut self) -> Result<Expr, ParseError> {
         if let Some(token) = self.tokens.peek() {
             match token.kind() {
                 TokenKind::Bang { .. } | TokenKind::Minus { .. } => {
                     let operator = self.tokens.next().expect("cannot fail");
-                    let right = self.unary()?;
+                    let right = self.nested(Self::unary)?;
                     return Ok(Expr::Unary {
-                        operator,
+                        operator: operator.into(),
                         right: Box::new(right),
                     });
                 }
                 _ => (),
             }
         }
-        self.primary()
+        self.call()
     }
 
-    fn primary(&mut self) -> Result<Expr<'a>, ParseError> {
-        if let Some(token) = self.tokens.next() {
-            return match token.kind() {
-                TokenKind::True { .. } => Ok(Expr::BooleanLiteral(true)),
-                TokenKind::False { .. } => Ok(Expr::BooleanLiteral(false)),
-                TokenKind::Nil { .. } => Ok(Expr::NoneLiteral),
-                TokenKind::Number { lexeme } => Ok(Expr::NumberLiteral(lexeme)),
-                TokenKind::String { lexeme } => Ok(Expr::StringLiteral(lexeme)),
+    fn call(&mut self) -> Result<Expr, ParseError> {
+        let mut expr = self.primary()?;
+        let depth = self.depth;
+
+        while let Some(token) = self.tokens.peek() {
+            match token.kind() {
                 TokenKind::LeftParen { .. } => {
-                    let expr = self.expression()?;
-                    if let Some(next_token) = self.tokens.next() {
-                        if !matches!(next_token.kind(), TokenKind::RightParen { .. }) {
-                            return Err(ParseError::ExpectedClosingParenAfterExpr {
-                                line: next_token.line(),
-                            });
-                        }
-                    } else {
-                        return Err(ParseError::UnexpectedEof);
-                    }
-                    Ok(Expr::Grouping {
-                        expression: Box::new(expr),
-                    })
-                }
-                _ => Err(ParseError::Internal(ParseErrorInternal::UnhandledToken {
-                    line: token.line(),
-                })),
-            };
+                    let opening_paren = self.tokens.next().expect("cannot fail");
+                    self.deepen()?;
+                    expr = self.finish_call(expr, opening_paren)?;
+                }
+                TokenKind::Dot { .. } => {
+                    self.tokens.next();
+                    self.deepen()?;
+                    let name = self.consume(
+                        |kind| matches!(kind, TokenKind::Identifier { .. }),
+                        |span| ParseError::ExpectedPropertyName { span },
+                    )?;
+                    expr = Expr::Get {
+                        object: Box::new(expr),
+                        name: name.into(),
+                    };
+                }
+                _ => break,
+            }
+        }
+
+        self.depth = depth;
+        Ok(expr)
+    }
+
+    /// Parses the arguments of a call to `callee` whose opening parenthesis has already been
+    /// consumed.
+    fn finish_call(
+        &mut self,
+        callee: Expr,
+        opening_paren: Token<'a>,
+    ) -> Result<Expr, ParseError> {
+        let mut arguments = vec![];
+        if !self.check(|kind| matches!(kind, TokenKind::RightParen { .. })) {
+            loop {
+                let argument = self.expression()?;
+                if arguments.len() >= MAX_ARGUMENTS {
+                    return Err(ParseError::TooManyArguments {
+                        max: MAX_ARGUMENTS,
+                        span: argument.span(),
+                    });
+                }
+                arguments.push(argument);
+                if self
+                    .next_if_kind(|kind| matches!(kind, TokenKind::Comma { .. }))
+                    .is_none()
+                {
+                    break;
+                }
+            }
+        }
+        let paren = self.consume(
+            |kind| matches!(kind, TokenKind::RightParen { .. }),
+            |span| ParseError::ExpectedClosingParenAfterArguments {
+                span,
+                opening: opening_paren.span(),
+            },
+        )?;
+        Ok(Expr::Call {
+            callee: Box::new(callee),
+            paren: paren.span(),
+            arguments,
+        })
+    }
+
+    fn primary(&mut self) -> Result<Expr, ParseError> {
+        let Some(token) = self.tokens.peek().copied() else {
+            return Err(ParseError::ExpectExpression {
+                span: self.tokens.end,
+            });
+        };
+        let span = token.span();
+        let single_token_expr = match token.kind() {
+            TokenKind::True { .. } => Some(Expr::BooleanLiteral { value: true, span }),
+            TokenKind::False { .. } => Some(Expr::BooleanLiteral { value: false, span }),
+            TokenKind::Nil { .. } => Some(Expr::NoneLiteral { span }),
+            TokenKind::Number { lexeme } => Some(Expr::NumberLiteral {
+                value: lexeme,
+                span,
+            }),
+            TokenKind::String { lexeme } => Some(Expr::StringLiteral {
+                value: string_value(lexeme).into(),
+                span,
+            }),
+            TokenKind::Identifier { .. } => Some(Expr::Variable {
+                name: token.into(),
+                depth: Depth::default(),
+            }),
+            TokenKind::This { .. } => Some(Expr::This {
+                keyword: token.into(),
+                depth: Depth::default(),
+            }),
+            _ => None,
+        };
+        if let Some(expr) = single_token_expr {
+            self.tokens.next();
+            return Ok(expr);
+        }
+
+        match token.kind() {
+            TokenKind::Super { .. } => {
+                self.tokens.next();
+                self.consume(
+                    |kind| matches!(kind, TokenKind::Dot { .. }),
+                    |span| ParseError::ExpectedDotAfterSuper { span },
+                )?;
+                let method = self.consume(
+                    |kind| matches!(kind, TokenKind::Identifier { .. }),
+                    |span| ParseError::ExpectedSuperclassMethodName { span },
+                )?;
+                Ok(Expr::Super {
+                    keyword: token.into(),
+                    method: method.into(),
+                    depth: Depth::default(),
+                })
+            }
+            TokenKind::LeftParen { .. } => {
+                self.tokens.next();
+                let expr = self.expression()?;
+                let closing_paren = self.consume(
+                    |kind| matches!(kind, TokenKind::RightParen { .. }),
+                    |closing| ParseError::ExpectedClosingParenAfterExpr {
+                        span: closing,
+                        opening: span,
+                    },
+                )?;
+                Ok(Expr::Grouping {
+                    expression: Box::new(expr),
+                    span: span.merge(closing_paren.span()),
+                })
+            }
+            // The offending token is left in place for `synchronize` to skip.
+            _ => Err(ParseError::ExpectExpression { span }),
+        }
+    }
+
+    /// Consumes the next token if it is of the expected kind, otherwise returns the error built
+    /// from the span of the offending token.
+    /// Parses a nested statement or expression with `parse`, failing instead if it is nested
+    /// too deeply.
+    fn nested<T>(
+        &mut self,
+        parse: impl FnOnce(&mut Self) -> Result<T, ParseError>,
+    ) -> Result<T, ParseError> {
+        let depth = self.depth;
+        self.deepen()?;
+        let result = stack::grow(|| parse(self));
+        self.depth = depth;
+        result
+    }
+
+    /// Goes one level deeper into the syntax tree, failing if it gets nested too deeply. Each
+    /// operator of a chain like `a + b + c` nests the tree further, just like parentheses do.
+    fn deepen(&mut self) -> Result<(), ParseError> {
+        if self.depth == MAX_NESTING {
+            let end = self.tokens.end;
+            let span = self.tokens.peek().map_or(end, |token| token.span());
+            return Err(ParseError::TooDeeplyNested {
+                max: MAX_NESTING,
+                span,
+            });
+        }
+        self.depth += 1;
+        Ok(())
+    }
+
+    fn consume(
+        &mut self,
+        expected: impl Fn(TokenKind<'a>) -> bool,
+        error: impl FnOnce(Span) -> ParseError,
+    ) -> Result<Token<'a>, ParseError> {
+        match self.tokens.peek() {
+            Some(token) if expected(token.kind()) => Ok(self.tokens.next().expect("cannot fail")),
+            Some(token) => Err(error(token.span())),
+            None => Err(ParseError::UnexpectedEof {
+                span: self.tokens.end,
+            }),
+        }
+    }
+
+    /// Consumes and returns the next token if it is of the expected kind.
+    fn next_if_kind(
+        &mut self,
+        expected: impl Fn(TokenKind<'a>) -> bool,
+    ) -> Option<Token<'a>> {
+        self.tokens.next_if(|token| expected(token.kind()))
+    }
+
+    /// Returns whether the next token is of the expected kind without consuming it.
+    fn check(
+        &mut self,
+        expected: impl Fn(TokenKind<'a>) -> bool,
+    ) -> bool {
+        self.tokens
+            .peek()
+            .is_some_and(|token| expected(token.kind()))
+    }
+
+    fn is_at_end(&mut self) -> bool {
+        matches!(
+            self.tokens.peek().map(|token| token.kind()),
+            None | Some(TokenKind::Eof)
+        )
+    }
+}
+
+/// The tokens being parsed with one token of lookahead, pulled from their source as needed.
+struct TokenStream<'a, I>
+where
+    I: Iterator<Item = Token<'a>>,
+{
+    tokens: Peekable<I>,
+    /// The span of the last token seen, where errors about running out of tokens are reported.
+    end: Span,
+}
+
+impl<'a, I> TokenStream<'a, I>
+where
+    I: Iterator<Item = Token<'a>>,
+{
+    fn peek(&mut self) -> Option<&Token<'a>> {
+        let token = self.tokens.peek();
+        if let Some(token) = token {
+            self.end = token.span();
         }
-        Err(ParseError::ExpectExpression)
+        token
+    }
+
+    fn next(&mut self) -> Option<Token<'a>> {
+        let token = self.tokens.next();
+        if let Some(token) = token {
+            self.end = token.span();
+        }
+        token
+    }
+
+    fn next_if(
+        &mut self,
+        func: impl FnOnce(&Token<'a>) -> bool,
+    ) -> Option<Token<'a>> {
+        let token = self.tokens.next_if(func);
+        if let Some(token) = token {
+            self.end = token.span();
+        }
+        token
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use itertools::Itertools;
+    use std::rc::Rc;
 
+    use itertools::process_results;
+
+    use crate::ast::ClassDecl;
     use crate::ast::Expr;
+    use crate::ast::FunctionDecl;
+    use crate::ast::Name;
+    use crate::ast::Operator;
+    use crate::ast::OperatorKind;
+    use crate::ast::Stmt;
+    use crate::error::LexicalError;
+    use crate::error::ParseError;
     use crate::lexer::Lexer;
     use crate::parser::Parser;
-    use crate::token::Token;
-    use crate::token::TokenKind;
+    use crate::span::Span;
 
     #[test]
     fn test_parsing_basic_expression() {
-        let input = "(1 + 2) * 3";
-        let tokens = Lexer::lex(input);
+        let input = "(1 + 2) * 3;";
+        let mut parser = Parser::new(Lexer::new(input).flatten());
+        let ast = parser.parse().unwrap();
+        assert_eq!(
+            ast,
+            vec![Stmt::Expression {
+                expression: Expr::Binary {
+                    left: Box::new(Expr::Grouping {
+                        expression: Box::new(Expr::Binary {
+                            left: Box::new(Expr::NumberLiteral {
+                                value: 1.0,
+                                span: Span::new(1, 2, 1, 2),
+                            }),
+                            operator: Operator::new(OperatorKind::Plus, Span::new(3, 4, 1, 4)),
+                            right: Box::new(Expr::NumberLiteral {
+                                value: 2.0,
+                                span: Span::new(5, 6, 1, 6),
+                            }),
+                        }),
+                        span: Span::new(0, 7, 1, 1),
+                    }),
+                    operator: Operator::new(OperatorKind::Star, Span::new(8, 9, 1, 9)),
+                    right: Box::new(Expr::NumberLiteral {
+                        value: 3.0,
+                        span: Span::new(10, 11, 1, 11),
+                    }),
+                },
+                span: Span::new(0, 12, 1, 1),
+            }]
+        )
+    }
+
+    #[test]
+    fn test_parsing_basic_expression_2() {
+        let input = "1 + 2 * 3;";
+        let mut parser = Parser::new(Lexer::new(input).flatten());
+        let ast = parser.parse().unwrap();
+        assert_eq!(
+            ast,
+            vec![Stmt::Expression {
+                expression: Expr::Binary {
+                    left: Box::new(Expr::NumberLiteral {
+                        value: 1.0,
+                        span: Span::new(0, 1, 1, 1),
+                    }),
+                    operator: Operator::new(OperatorKind::Plus, Span::new(2, 3, 1, 3)),
+                    right: Box::new(Expr::Binary {
+                        left: Box::new(Expr::NumberLiteral {
+                            value: 2.0,
+                            span: Span::new(4, 5, 1, 5),
+                        }),
+                        operator: Operator::new(OperatorKind::Star, Span::new(6, 7, 1, 7)),
+                        right: Box::new(Expr::NumberLiteral {
+                            value: 3.0,
+                            span: Span::new(8, 9, 1, 9),
+                        }),
+                    }),
+                },
+                span: Span::new(0, 10, 1, 1),
+            }]
+        )
+    }
+
+    #[test]
+    fn test_parsing_print_statements() {
+        let input = "print 1;\nprint \"two\";";
+        let mut parser = Parser::new(Lexer::new(input).flatten());
+        let ast = parser.parse().unwrap();
+        assert_eq!(
+            ast,
+            vec![
+                Stmt::Print {
+                    expression: Expr::NumberLiteral {
+                        value: 1.0,
+                        span: Span::new(6, 7, 1, 7),
+                    },
+                    span: Span::new(0, 8, 1, 1),
+                },
+                Stmt::Print {
+                    expression: Expr::StringLiteral {
+                        value: "two".into(),
+                        span: Span::new(15, 20, 2, 7),
+                    },
+                    span: Span::new(9, 21, 2, 1),
+                },
+            ]
+        )
+    }
+
+    #[test]
+    fn test_parsing_lazily_lexed_tokens_stops_at_the_first_lexical_error() {
+        let input = "print 1;\nprint @;\nprint \"never lexed";
+        let result = process_results(Lexer::new(input), |tokens| Parser::new(tokens).parse());
+
+        assert!(matches!(
+            result,
+            Err(LexicalError::UnexpectedCharacter { char: '@', .. })
+        ));
+    }
+
+    #[test]
+    fn test_parsing_statement_without_semicolon_fails() {
+        let input = "print 1\n2;";
+        let mut parser = Parser::new(Lexer::new(input).flatten());
+        assert!(matches!(
+            parser.parse().unwrap_err()[..],
+            [ParseError::ExpectedSemicolonAfterValue {
+                span: Span {
+                    line: 2,
+                    column: 1,
+                    ..
+                }
+            }]
+        ))
+    }
+
+    #[test]
+    fn test_parsing_variable_declaration_and_assignment() {
+        let input = "var a = 1;\nvar b;\na = b = 2;";
+        let mut parser = Parser::new(Lexer::new(input).flatten());
+        let ast = parser.parse().unwrap();
+        assert_eq!(
+            ast,
+            vec![
+                Stmt::Var {
+                    name: Name::new("a", Span::new(4, 5, 1, 5)),
+                    initializer: Some(Expr::NumberLiteral {
+                        value: 1.0,
+                        span: Span::new(8, 9, 1, 9),
+                    }),
+                    span: Span::new(0, 10, 1, 1),
+                },
+                Stmt::Var {
+                    name: Name::new("b", Span::new(15, 16, 2, 5)),
+                    initializer: None,
+                    span: Span::new(11, 17, 2, 1),
+                },
+                Stmt::Expression {
+                    expression: Expr::Assign {
+                        name: Name::new("a", Span::new(18, 19, 3, 1)),
+                        value: Box::new(Expr::Assign {
+                            name: Name::new("b", Span::new(22, 23, 3, 5)),
+                            value: Box::new(Expr::NumberLiteral {
+                                value: 2.0,
+                                span: Span::new(26, 27, 3, 9),
+                            }),
+                            depth: Default::default(),
+                        }),
+                        depth: Default::default(),
+                    },
+                    span: Span::new(18, 28, 3, 1),
+                },
+            ]
+        )
+    }
+
+    #[test]
+    fn test_parsing_invalid_assignment_target_fails() {
+        let input = "1 + a = 2;";
+        let mut parser = Parser::new(Lexer::new(input).flatten());
+        assert!(matches!(
+            parser.parse().unwrap_err()[..],
+            [ParseError::InvalidAssignmentTarget {
+                span: Span { line: 1, .. }
+            }]
+        ))
+    }
+
+    #[test]
+    fn test_parsing_nested_blocks() {
+        let input = "{ var a; { print a; } }";
+        let mut parser = Parser::new(Lexer::new(input).flatten());
+        let ast = parser.parse().unwrap();
+        assert_eq!(
+            ast,
+            vec![Stmt::Block {
+                statements: vec![
+                    Stmt::Var {
+                        name: Name::new("a", Span::new(6, 7, 1, 7)),
+                        initializer: None,
+                        span: Span::new(2, 8, 1, 3),
+                    },
+                    Stmt::Block {
+                        statements: vec![Stmt::Print {
+                            expression: Expr::Variable {
+                                name: Name::new("a", Span::new(17, 18, 1, 18)),
+                                depth: Default::default(),
+                            },
+                            span: Span::new(11, 19, 1, 12),
+                        }],
+                        span: Span::new(9, 21, 1, 10),
+                    },
+                ],
+                span: Span::new(0, 23, 1, 1),
+            }]
+        )
+    }
+
+    #[test]
+    fn test_parsing_unterminated_block_fails() {
+        let input = "{ print 1;";
+        let mut parser = Parser::new(Lexer::new(input).flatten());
+        assert!(matches!(
+            parser.parse().unwrap_err()[..],
+            [ParseError::ExpectedClosingBraceAfterBlock {
+                span: Span { line: 1, .. },
+                opening: Span {
+                    start: 0,
+                    end: 1,
+                    ..
+                }
+            }]
+        ))
+    }
+
+    #[test]
+    fn test_parsing_logical_operator_precedence() {
+        let input = "a or b and c;";
+        let mut parser = Parser::new(Lexer::new(input).flatten());
+        let ast = parser.parse().unwrap();
+        assert_eq!(
+            ast,
+            vec![Stmt::Expression {
+                expression: Expr::Logical {
+                    left: Box::new(Expr::Variable {
+                        name: Name::new("a", Span::new(0, 1, 1, 1)),
+                        depth: Default::default(),
+                    }),
+                    operator: Operator::new(OperatorKind::Or, Span::new(2, 4, 1, 3)),
+                    right: Box::new(Expr::Logical {
+                        left: Box::new(Expr::Variable {
+                            name: Name::new("b", Span::new(5, 6, 1, 6)),
+                            depth: Default::default(),
+                        }),
+                        operator: Operator::new(OperatorKind::And, Span::new(7, 10, 1, 8)),
+                        right: Box::new(Expr::Variable {
+                            name: Name::new("c", Span::new(11, 12, 1, 12)),
+                            depth: Default::default(),
+                        }),
+                    }),
+                },
+                span: Span::new(0, 13, 1, 1),
+            }]
+        )
+    }
 
-        let mut parser = Parser::new(tokens.into_iter().flatten().collect_vec());
+    #[test]
+    fn test_parsing_for_loop_desugars_into_while() {
+        let input = "for (;;) print 1;";
+        let mut parser = Parser::new(Lexer::new(input).flatten());
         let ast = parser.parse().unwrap();
         assert_eq!(
             ast,
-            Expr::Binary {
-                left: Box::new(Expr::Grouping {
-                    expression: Box::new(Expr::Binary {
-                        left: Box::new(Expr::NumberLiteral(1.0)),
-                        operator: Token::new(TokenKind::Plus { lexeme: "+" }, 1),
-                        right: Box::new(Expr::NumberLiteral(2.0)),
-                    })
+            vec![Stmt::While {
+                condition: Expr::BooleanLiteral {
+                    value: true,
+                    span: Span::new(6, 7, 1, 7),
+                },
+                body: Box::new(Stmt::Print {
+                    expression: Expr::NumberLiteral {
+                        value: 1.0,
+                        span: Span::new(15, 16, 1, 16),
+                    },
+                    span: Span::new(9, 17, 1, 10),
                 }),
-                operator: Token::new(TokenKind::Star { lexeme: "*" }, 1),
-                right: Box::new(Expr::NumberLiteral(3.0)),
-            }
+                span: Span::new(0, 17, 1, 1),
+            }]
         )
     }
 
     #[test]
-    fn test_parsing_basic_expression_2() {
-        let input = "1 + 2 * 3";
-        let tokens = Lexer::lex(input);
+    fn test_parsing_if_without_parenthesis_fails() {
+        let input = "if true print 1;";
+        let mut parser = Parser::new(Lexer::new(input).flatten());
+        assert!(matches!(
+            parser.parse().unwrap_err()[..],
+            [ParseError::ExpectedOpeningParenAfterKeyword {
+                keyword: "if",
+                span: Span { line: 1, .. }
+            }]
+        ))
+    }
+
+    #[test]
+    fn test_parsing_function_declaration_and_call() {
+        let input = "fun f(a, b) { return a; }\nf(1)(2);";
+        let mut parser = Parser::new(Lexer::new(input).flatten());
+        let ast = parser.parse().unwrap();
+        assert_eq!(
+            ast,
+            vec![
+                Stmt::Function(Rc::new(FunctionDecl {
+                    name: Name::new("f", Span::new(4, 5, 1, 5)),
+                    params: vec![
+                        Name::new("a", Span::new(6, 7, 1, 7)),
+                        Name::new("b", Span::new(9, 10, 1, 10)),
+                    ],
+                    body: vec![Stmt::Return {
+                        keyword: Span::new(14, 20, 1, 15),
+                        value: Some(Expr::Variable {
+                            name: Name::new("a", Span::new(21, 22, 1, 22)),
+                            depth: Default::default(),
+                        }),
+                        span: Span::new(14, 23, 1, 15),
+                    }],
+                    span: Span::new(0, 25, 1, 1),
+                })),
+                Stmt::Expression {
+                    expression: Expr::Call {
+                        callee: Box::new(Expr::Call {
+                            callee: Box::new(Expr::Variable {
+                                name: Name::new("f", Span::new(26, 27, 2, 1)),
+                                depth: Default::default(),
+                            }),
+                            paren: Span::new(29, 30, 2, 4),
+                            arguments: vec![Expr::NumberLiteral {
+                                value: 1.0,
+                                span: Span::new(28, 29, 2, 3),
+                            }],
+                        }),
+                        paren: Span::new(32, 33, 2, 7),
+                        arguments: vec![Expr::NumberLiteral {
+                            value: 2.0,
+                            span: Span::new(31, 32, 2, 6),
+                        }],
+                    },
+                    span: Span::new(26, 34, 2, 1),
+                },
+            ]
+        )
+    }
+
+    #[test]
+    fn test_parsing_call_without_closing_paren_fails() {
+        let input = "f(1, 2;";
+        let mut parser = Parser::new(Lexer::new(input).flatten());
+        assert!(matches!(
+            parser.parse().unwrap_err()[..],
+            [ParseError::ExpectedClosingParenAfterArguments {
+                span: Span { line: 1, .. },
+                opening: Span {
+                    start: 1,
+                    end: 2,
+                    ..
+                }
+            }]
+        ))
+    }
+
+    #[test]
+    fn test_parsing_property_access_and_assignment() {
+        let input = "this.a.b = c.d;";
+        let mut parser = Parser::new(Lexer::new(input).flatten());
+        let ast = parser.parse().unwrap();
+        assert_eq!(
+            ast,
+            vec![Stmt::Expression {
+                expression: Expr::Set {
+                    object: Box::new(Expr::Get {
+                        object: Box::new(Expr::This {
+                            keyword: Name::new("this", Span::new(0, 4, 1, 1)),
+                            depth: Default::default(),
+                        }),
+                        name: Name::new("a", Span::new(5, 6, 1, 6)),
+                    }),
+                    name: Name::new("b", Span::new(7, 8, 1, 8)),
+                    value: Box::new(Expr::Get {
+                        object: Box::new(Expr::Variable {
+                            name: Name::new("c", Span::new(11, 12, 1, 12)),
+                            depth: Default::default(),
+                        }),
+                        name: Name::new("d", Span::new(13, 14, 1, 14)),
+                    }),
+                },
+                span: Span::new(0, 15, 1, 1),
+            }]
+        )
+    }
 
-        let mut parser = Parser::new(tokens.into_iter().flatten().collect_vec());
+    #[test]
+    fn test_parsing_class_declaration() {
+        let input = "class A { m() {} }";
+        let mut parser = Parser::new(Lexer::new(input).flatten());
         let ast = parser.parse().unwrap();
         assert_eq!(
             ast,
-            Expr::Binary {
-                left: Box::new(Expr::NumberLiteral(1.0)),
-                operator: Token::new(TokenKind::Plus { lexeme: "+" }, 1),
-                right: Box::new(Expr::Binary {
-                    left: Box::new(Expr::NumberLiteral(2.0)),
-                    operator: Token::new(TokenKind::Star { lexeme: "*" }, 1),
-                    right: Box::new(Expr::NumberLiteral(3.0)),
+            vec![Stmt::Class(ClassDecl {
+                name: Name::new("A", Span::new(6, 7, 1, 7)),
+                superclass: None,
+                methods: vec![Rc::new(FunctionDecl {
+                    name: Name::new("m", Span::new(10, 11, 1, 11)),
+                    params: vec![],
+                    body: vec![],
+                    span: Span::new(10, 16, 1, 11),
+                })],
+                span: Span::new(0, 18, 1, 1),
+            })]
+        )
+    }
+
+    #[test]
+    fn test_parsing_subclass_with_super_call() {
+        let input = "class B < A { m() { super.m(); } }";
+        let mut parser = Parser::new(Lexer::new(input).flatten());
+        let ast = parser.parse().unwrap();
+        assert_eq!(
+            ast,
+            vec![Stmt::Class(ClassDecl {
+                name: Name::new("B", Span::new(6, 7, 1, 7)),
+                superclass: Some(Expr::Variable {
+                    name: Name::new("A", Span::new(10, 11, 1, 11)),
+                    depth: Default::default(),
                 }),
-            }
+                methods: vec![Rc::new(FunctionDecl {
+                    name: Name::new("m", Span::new(14, 15, 1, 15)),
+                    params: vec![],
+                    body: vec![Stmt::Expression {
+                        expression: Expr::Call {
+                            callee: Box::new(Expr::Super {
+                                keyword: Name::new("super", Span::new(20, 25, 1, 21)),
+                                method: Name::new("m", Span::new(26, 27, 1, 27)),
+                                depth: Default::default(),
+                            }),
+                            paren: Span::new(28, 29, 1, 29),
+                            arguments: vec![],
+                        },
+                        span: Span::new(20, 30, 1, 21),
+                    }],
+                    span: Span::new(14, 32, 1, 15),
+                })],
+                span: Span::new(0, 34, 1, 1),
+            })]
         )
     }
+
+    #[test]
+    fn test_parsing_class_inheriting_from_itself_fails() {
+        let input = "class A < A {}";
+        let mut parser = Parser::new(Lexer::new(input).flatten());
+        assert!(matches!(
+            parser.parse().unwrap_err()[..],
+            [ParseError::ClassInheritsFromItself {
+                span: Span { column: 11, .. },
+                class_name: Span { column: 7, .. }
+            }]
+        ))
+    }
+
+    #[test]
+    fn test_parsing_reports_every_syntax_error() {
+        let input = "var = 1;\nprint 2;\nfun () {}\nprint (3;\nprint 4;";
+        let mut parser = Parser::new(Lexer::new(input).flatten());
+        assert!(matches!(
+            parser.parse().unwrap_err()[..],
+            [
+                ParseError::ExpectedVariableName {
+                    span: Span { line: 1, .. }
+                },
+                ParseError::ExpectedFunctionName {
+                    span: Span { line: 3, .. }
+                },
+                ParseError::ExpectedClosingParenAfterExpr {
+                    span: Span { line: 4, .. },
+                    ..
+                },
+            ]
+        ))
+    }
+
+    fn parse_nested(source: &str) -> Result<Vec<Stmt>, Vec<ParseError>> {
+        Parser::new(Lexer::new(source).flatten()).parse()
+    }
+
+    #[test]
+    fn test_parsing_deeply_nested_code_fails() {
+        let parens = format!("print {}1{};", "(".repeat(300), ")".repeat(300));
+        let blocks = format!("{}{}", "{".repeat(300), "}".repeat(300));
+        let negations = format!("print {}1;", "-".repeat(300));
+        let chain = format!("print 1{};", "+1".repeat(300));
+        let properties = format!("print a{};", ".b".repeat(300));
+
+        for source in [parens, blocks, negations, chain, properties] {
+            assert!(
+                matches!(
+                    parse_nested(&source).unwrap_err()[..],
+                    [ParseError::TooDeeplyNested { max: 256, .. }, ..]
+                ),
+                "{source}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_parsing_nesting_below_the_limit_succeeds() {
+        let parens = format!("print {}1{};", "(".repeat(200), ")".repeat(200));
+        let chain = format!("print 1{};", "+1".repeat(200));
+        assert!(parse_nested(&parens).is_ok());
+        assert!(parse_nested(&chain).is_ok());
+    }
+
+    #[test]
+    fn test_parsing_recovers_nesting_depth_after_errors() {
+        let source = format!(
+            "{}print {}1{};",
+            "print ((1;\n".repeat(100),
+            "(".repeat(200),
+            ")".repeat(200)
+        );
+        let errors = parse_nested(&source).unwrap_err();
+        assert_eq!(errors.len(), 100);
+        assert!(errors
+            .iter()
+            .all(|error| matches!(error, ParseError::ExpectedClosingParenAfterExpr { .. })));
+    }
 }
@@ -0,0 +1,342 @@
+use std::fmt::Display;
+use std::fmt::Formatter;
+use std::rc::Rc;
+
+use itertools::Itertools;
+
+use crate::ast::ClassDecl;
+use crate::ast::Depth;
+use crate::ast::Expr;
+use crate::ast::FunctionDecl;
+use crate::ast::Name;
+use crate::ast::Operator;
+use crate::ast::Stmt;
+use crate::span::Span;
+use crate::visitor::ExprVisitor;
+use crate::visitor::StmtVisitor;
+
+/// Prints syntax trees as parenthesized S-expressions that make the structure explicit, for
+/// example `(* (group (+ 1 2)) 3)` for `(1 + 2) * 3`.
+pub(crate) struct AstPrinter;
+
+impl AstPrinter {
+    fn parenthesize<'e>(
+        &mut self,
+        name: &str,
+        expressions: impl IntoIterator<Item = &'e Expr>,
+    ) -> String {
+        let mut printed = format!("({name}");
+        for expression in expressions {
+            printed.push(' ');
+            printed.push_str(&expression.accept(self));
+        }
+        printed.push(')');
+        printed
+    }
+
+    fn function(
+        &mut self,
+        declaration: &FunctionDecl,
+    ) -> String {
+        let params = declaration
+            .params
+            .iter()
+            .map(|param| param.identifier())
+            .join(" ");
+        let mut printed = format!("(fun {} ({params})", declaration.name.identifier());
+        for statement in &declaration.body {
+            printed.push(' ');
+            printed.push_str(&statement.accept(self));
+        }
+        printed.push(')');
+        printed
+    }
+}
+
+impl ExprVisitor for AstPrinter {
+    type Output = String;
+
+    fn visit_binary(
+        &mut self,
+        left: &Expr,
+        operator: &Operator,
+        right: &Expr,
+    ) -> Self::Output {
+        self.parenthesize(operator.kind().lexeme(), [left, right])
+    }
+
+    fn visit_unary(
+        &mut self,
+        operator: &Operator,
+        right: &Expr,
+    ) -> Self::Output {
+        self.parenthesize(operator.kind().lexeme(), [right])
+    }
+
+    fn visit_logical(
+        &mut self,
+        left: &Expr,
+        operator: &Operator,
+        right: &Expr,
+    ) -> Self::Output {
+        self.parenthesize(operator.kind().lexeme(), [left, right])
+    }
+
+    fn visit_grouping(
+        &mut self,
+        expression: &Expr,
+    ) -> Self::Output {
+        self.parenthesize("group", [expression])
+    }
+
+    fn visit_variable(
+        &mut self,
+        name: &Name,
+        _depth: &Depth,
+    ) -> Self::Output {
+        name.identifier().to_string()
+    }
+
+    fn visit_assign(
+        &mut self,
+        name: &Name,
+        value: &Expr,
+        _depth: &Depth,
+    ) -> Self::Output {
+        format!("(= {} {})", name.identifier(), value.accept(self))
+    }
+
+    fn visit_get(
+        &mut self,
+        object: &Expr,
+        name: &Name,
+    ) -> Self::Output {
+        format!("(. {} {})", object.accept(self), name.identifier())
+    }
+
+    fn visit_set(
+        &mut self,
+        object: &Expr,
+        name: &Name,
+        value: &Expr,
+    ) -> Self::Output {
+        format!(
+            "(= (. {} {}) {})",
+            object.accept(self),
+            name.identifier(),
+            value.accept(self)
+        )
+    }
+
+    fn visit_this(
+        &mut self,
+        _keyword: &Name,
+        _depth: &Depth,
+    ) -> Self::Output {
+        "this".to_string()
+    }
+
+    fn visit_super(
+        &mut self,
+        _keyword: &Name,
+        method: &Name,
+        _depth: &Depth,
+    ) -> Self::Output {
+        format!("(. super {})", method.identifier())
+    }
+
+    fn visit_call(
+        &mut self,
+        callee: &Expr,
+        _paren: Span,
+        arguments: &[Expr],
+    ) -> Self::Output {
+        self.parenthesize("call", std::iter::once(callee).chain(arguments))
+    }
+
+    fn visit_string_literal(
+        &mut self,
+        value: &Rc<str>,
+    ) -> Self::Output {
+        format!("{value:?}")
+    }
+
+    fn visit_boolean_literal(
+        &mut self,
+        value: bool,
+    ) -> Self::Output {
+        value.to_string()
+    }
+
+    fn visit_none_literal(&mut self) -> Self::Output {
+        "nil".to_string()
+    }
+
+    fn visit_number_literal(
+        &mut self,
+        value: f64,
+    ) -> Self::Output {
+        value.to_string()
+    }
+}
+
+impl StmtVisitor for AstPrinter {
+    type Output = String;
+
+    fn visit_expression_statement(
+        &mut self,
+        expression: &Expr,
+    ) -> Self::Output {
+        self.parenthesize("expr", [expression])
+    }
+
+    fn visit_print(
+        &mut self,
+        expression: &Expr,
+        _span: Span,
+    ) -> Self::Output {
+        self.parenthesize("print", [expression])
+    }
+
+    fn visit_var(
+        &mut self,
+        name: &Name,
+        initializer: Option<&Expr>,
+    ) -> Self::Output {
+        self.parenthesize(&format!("var {}", name.identifier()), initializer)
+    }
+
+    fn visit_block(
+        &mut self,
+        statements: &[Stmt],
+    ) -> Self::Output {
+        let mut printed = "(block".to_string();
+        for statement in statements {
+            printed.push(' ');
+            printed.push_str(&statement.accept(self));
+        }
+        printed.push(')');
+        printed
+    }
+
+    fn visit_if(
+        &mut self,
+        condition: &Expr,
+        then_branch: &Stmt,
+        else_branch: Option<&Stmt>,
+    ) -> Self::Output {
+        let mut printed = format!(
+            "(if {} {}",
+            condition.accept(self),
+            then_branch.accept(self)
+        );
+        if let Some(else_branch) = else_branch {
+            printed.push(' ');
+            printed.push_str(&else_branch.accept(self));
+        }
+        printed.push(')');
+        printed
+    }
+
+    fn visit_while(
+        &mut self,
+        condition: &Expr,
+        body: &Stmt,
+    ) -> Self::Output {
+        format!("(while {} {})", condition.accept(self), body.accept(self))
+    }
+
+    fn visit_function(
+        &mut self,
+        declaration: &Rc<FunctionDecl>,
+    ) -> Self::Output {
+        self.function(declaration)
+    }
+
+    fn visit_class(
+        &mut self,
+        declaration: &ClassDecl,
+    ) -> Self::Output {
+        let mut printed = format!("(class {}", declaration.name.identifier());
+        if let Some(superclass) = &declaration.superclass {
+            printed.push_str(" < ");
+            printed.push_str(&superclass.accept(self));
+        }
+        for method in &declaration.methods {
+            printed.push(' ');
+            printed.push_str(&self.function(method));
+        }
+        printed.push(')');
+        printed
+    }
+
+    fn visit_return(
+        &mut self,
+        _keyword: Span,
+        value: Option<&Expr>,
+    ) -> Self::Output {
+        self.parenthesize("return", value)
+    }
+}
+
+impl Display for Expr {
+    fn fmt(
+        &self,
+        f: &mut Formatter<'_>,
+    ) -> std::fmt::Result {
+        f.write_str(&self.accept(&mut AstPrinter))
+    }
+}
+
+impl Display for Stmt {
+    fn fmt(
+        &self,
+        f: &mut Formatter<'_>,
+    ) -> std::fmt::Result {
+        f.write_str(&self.accept(&mut AstPrinter))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use itertools::Itertools;
+
+    use crate::lexer::Lexer;
+    use crate::parser::Parser;
+
+    fn print(source: &str) -> String {
+        Parser::new(Lexer::new(source).flatten())
+            .parse()
+            .unwrap()
+            .iter()
+            .join("\n")
+    }
+
+    #[test]
+    fn printing_makes_precedence_explicit() {
+        assert_eq!(
+            print("(1 + 2) * 3 - -4 / 2;"),
+            "(expr (- (* (group (+ 1 2)) 3) (/ (- 4) 2)))"
+        );
+        assert_eq!(
+            print("a = b or c and !d == e;"),
+            "(expr (= a (or b (and c (== (! d) e)))))"
+        );
+    }
+
+    #[test]
+    fn printing_covers_every_statement() {
+        let source = "var a = \"hi\";\n\
+                      class B < A { init(x) { this.x = x; return; } }\n\
+                      fun f() { if (nil) print true; else { print super.y; } return f(1, 2.5); }\n\
+                      while (false) a;";
+
+        assert_eq!(
+            print(source),
+            "(var a \"hi\")\n\
+             (class B < A (fun init (x) (expr (= (. this x) x)) (return)))\n\
+             (fun f () (if nil (print true) (block (print (. super y)))) (return (call f 1 2.5)))\n\
+             (while false (expr a))"
+        );
+    }
+}
@@ -0,0 +1,583 @@
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use crate::ast::ClassDecl;
+use crate::ast::Depth;
+use crate::ast::Expr;
+use crate::ast::FunctionDecl;
+use crate::ast::Name;
+use crate::ast::Operator;
+use crate::ast::Stmt;
+use crate::class::INITIALIZER;
+use crate::error::ResolveError;
+use crate::span::Span;
+use crate::stack;
+use crate::visitor::ExprVisitor;
+use crate::visitor::StmtVisitor;
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum FunctionKind {
+    None,
+    Function,
+    Method,
+    Initializer,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum ClassKind {
+    None,
+    Class,
+    Subclass,
+}
+
+/// A local variable in one of the scopes tracked by the resolver.
+#[derive(Debug, Copy, Clone)]
+struct Local {
+    /// Where the variable is declared.
+    span: Span,
+    /// Whether the initializer of the variable has finished resolving.
+    is_defined: bool,
+}
+
+impl Local {
+    /// A variable that the language binds implicitly, like `this`, attributed to `span`.
+    fn implicit(span: Span) -> Self {
+        Self {
+            span,
+            is_defined: true,
+        }
+    }
+}
+
+/// A static pass between parsing and interpreting that binds every variable use to the scope
+/// declaring it and rejects programs that are syntactically valid but semantically meaningless.
+pub(crate) struct Resolver {
+    /// The local scopes around the node being resolved, innermost last. Globals are not tracked.
+    scopes: Vec<HashMap<Rc<str>, Local>>,
+    current_function: FunctionKind,
+    current_class: ClassKind,
+}
+
+impl Resolver {
+    /// Resolves `statements`, storing the scope depth of every local variable use in the tree.
+    pub(crate) fn resolve(statements: &[Stmt]) -> Result<(), ResolveError> {
+        let mut resolver = Self {
+            scopes: vec![],
+            current_function: FunctionKind::None,
+            current_class: ClassKind::None,
+        };
+        resolver.resolve_statements(statements)
+    }
+
+    fn resolve_statements(
+        &mut self,
+        statements: &[Stmt],
+    ) -> Result<(), ResolveError> {
+        statements
+            .iter()
+            .try_for_each(|statement| self.resolve_statement(statement))
+    }
+
+    fn resolve_statement(
+        &mut self,
+        statement: &Stmt,
+    ) -> Result<(), ResolveError> {
+        stack::grow(|| statement.accept(self))
+    }
+
+    fn resolve_class(
+        &mut self,
+        declaration: &ClassDecl,
+    ) -> Result<(), ResolveError> {
+        let enclosing_class = self.current_class;
+        self.current_class = ClassKind::Class;
+        let result = self.resolve_class_inner(declaration);
+        self.current_class = enclosing_class;
+        result
+    }
+
+    fn resolve_class_inner(
+        &mut self,
+        declaration: &ClassDecl,
+    ) -> Result<(), ResolveError> {
+        self.declare(&declaration.name)?;
+        self.define(&declaration.name);
+
+        if let Some(superclass) = &declaration.superclass {
+            self.current_class = ClassKind::Subclass;
+            self.resolve_expression(superclass)?;
+            self.scopes.push(HashMap::from([(
+                "super".into(),
+                Local::implicit(superclass.span()),
+            )]));
+        }
+
+        self.scopes.push(HashMap::from([(
+            "this".into(),
+            Local::implicit(declaration.name.span()),
+        )]));
+        let result = declaration.methods.iter().try_for_each(|method| {
+            let kind = if method.name.identifier() == INITIALIZER {
+                FunctionKind::Initializer
+            } else {
+                FunctionKind::Method
+            };
+            self.resolve_function(method, kind)
+        });
+        self.scopes.pop();
+
+        if declaration.superclass.is_some() {
+            self.scopes.pop();
+        }
+        result
+    }
+
+    fn resolve_function(
+        &mut self,
+        declaration: &FunctionDecl,
+        kind: FunctionKind,
+    ) -> Result<(), ResolveError> {
+        let enclosing_function = self.current_function;
+        self.current_function = kind;
+        self.scopes.push(HashMap::new());
+        let result = declaration
+            .params
+            .iter()
+            .try_for_each(|param| {
+                self.declare(param)?;
+                self.define(param);
+                Ok(())
+            })
+            .and_then(|()| self.resolve_statements(&declaration.body));
+        self.scopes.pop();
+        self.current_function = enclosing_function;
+        result
+    }
+
+    fn resolve_expression(
+        &mut self,
+        expr: &Expr,
+    ) -> Result<(), ResolveError> {
+        stack::grow(|| expr.accept(self))
+    }
+
+    /// Adds `name` to the innermost scope, marking it as not yet ready for use.
+    fn declare(
+        &mut self,
+        name: &Name,
+    ) -> Result<(), ResolveError> {
+        let Some(scope) = self.scopes.last_mut() else {
+            return Ok(());
+        };
+        let local = Local {
+            span: name.span(),
+            is_defined: false,
+        };
+        if let Some(previous) = scope.insert(name.shared(), local) {
+            return Err(ResolveError::DuplicateLocal {
+                span: name.span(),
+                name: name.identifier().to_string(),
+                previous: previous.span,
+            });
+        }
+        Ok(())
+    }
+
+    /// Marks `name` in the innermost scope as fully initialized.
+    fn define(
+        &mut self,
+        name: &Name,
+    ) {
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.insert(
+                name.shared(),
+                Local {
+                    span: name.span(),
+                    is_defined: true,
+                },
+            );
+        }
+    }
+
+    /// Records how many scopes away from the innermost one `name` is declared. Variables that are
+    /// not found in any local scope are assumed to be globals and keep a depth of `None`.
+    fn resolve_local(
+        &mut self,
+        name: &Name,
+        depth: &Depth,
+    ) {
+        let found = self
+            .scopes
+            .iter()
+            .rev()
+            .position(|scope| scope.contains_key(name.identifier()));
+        depth.set(found);
+    }
+}
+
+impl StmtVisitor for Resolver {
+    type Output = Result<(), ResolveError>;
+
+    fn visit_expression_statement(
+        &mut self,
+        expression: &Expr,
+    ) -> Self::Output {
+        self.resolve_expression(expression)
+    }
+
+    fn visit_print(
+        &mut self,
+        expression: &Expr,
+        _span: Span,
+    ) -> Self::Output {
+        self.resolve_expression(expression)
+    }
+
+    fn visit_var(
+        &mut self,
+        name: &Name,
+        initializer: Option<&Expr>,
+    ) -> Self::Output {
+        self.declare(name)?;
+        if let Some(initializer) = initializer {
+            self.resolve_expression(initializer)?;
+        }
+        self.define(name);
+        Ok(())
+    }
+
+    fn visit_block(
+        &mut self,
+        statements: &[Stmt],
+    ) -> Self::Output {
+        self.scopes.push(HashMap::new());
+        let result = self.resolve_statements(statements);
+        self.scopes.pop();
+        result
+    }
+
+    fn visit_if(
+        &mut self,
+        condition: &Expr,
+        then_branch: &Stmt,
+        else_branch: Option<&Stmt>,
+    ) -> Self::Output {
+        self.resolve_expression(condition)?;
+        self.resolve_statement(then_branch)?;
+        if let Some(else_branch) = else_branch {
+            self.resolve_statement(else_branch)?;
+        }
+        Ok(())
+    }
+
+    fn visit_while(
+        &mut self,
+        condition: &Expr,
+        body: &Stmt,
+    ) -> Self::Output {
+        self.resolve_expression(condition)?;
+        self.resolve_statement(body)
+    }
+
+    fn visit_function(
+        &mut self,
+        declaration: &Rc<FunctionDecl>,
+    ) -> Self::Output {
+        // Declared and defined eagerly so the function can refer to itself recursively.
+        self.declare(&declaration.name)?;
+        self.define(&declaration.name);
+        self.resolve_function(declaration, FunctionKind::Function)
+    }
+
+    fn visit_class(
+        &mut self,
+        declaration: &ClassDecl,
+    ) -> Self::Output {
+        self.resolve_class(declaration)
+    }
+
+    fn visit_return(
+        &mut self,
+        keyword: Span,
+        value: Option<&Expr>,
+    ) -> Self::Output {
+        match self.current_function {
+            FunctionKind::None => return Err(ResolveError::ReturnAtTopLevel { span: keyword }),
+            FunctionKind::Initializer if value.is_some() => {
+                return Err(ResolveError::ReturnValueFromInitializer { span: keyword })
+            }
+            _ => (),
+        }
+        if let Some(value) = value {
+            self.resolve_expression(value)?;
+        }
+        Ok(())
+    }
+}
+
+impl ExprVisitor for Resolver {
+    type Output = Result<(), ResolveError>;
+
+    fn visit_binary(
+        &mut self,
+        left: &Expr,
+        _operator: &Operator,
+        right: &Expr,
+    ) -> Self::Output {
+        self.resolve_expression(left)?;
+        self.resolve_expression(right)
+    }
+
+    fn visit_unary(
+        &mut self,
+        _operator: &Operator,
+        right: &Expr,
+    ) -> Self::Output {
+        self.resolve_expression(right)
+    }
+
+    fn visit_logical(
+        &mut self,
+        left: &Expr,
+        _operator: &Operator,
+        right: &Expr,
+    ) -> Self::Output {
+        self.resolve_expression(left)?;
+        self.resolve_expression(right)
+    }
+
+    fn visit_grouping(
+        &mut self,
+        expression: &Expr,
+    ) -> Self::Output {
+        self.resolve_expression(expression)
+    }
+
+    fn visit_variable(
+        &mut self,
+        name: &Name,
+        depth: &Depth,
+    ) -> Self::Output {
+        let is_being_initialized = self
+            .scopes
+            .last()
+            .and_then(|scope| scope.get(name.identifier()))
+            .is_some_and(|local| !local.is_defined);
+        if is_being_initialized {
+            return Err(ResolveError::ReadLocalInOwnInitializer {
+                span: name.span(),
+                name: name.identifier().to_string(),
+            });
+        }
+        self.resolve_local(name, depth);
+        Ok(())
+    }
+
+    fn visit_assign(
+        &mut self,
+        name: &Name,
+        value: &Expr,
+        depth: &Depth,
+    ) -> Self::Output {
+        self.resolve_expression(value)?;
+        self.resolve_local(name, depth);
+        Ok(())
+    }
+
+    fn visit_get(
+        &mut self,
+        object: &Expr,
+        _name: &Name,
+    ) -> Self::Output {
+        self.resolve_expression(object)
+    }
+
+    fn visit_set(
+        &mut self,
+        object: &Expr,
+        _name: &Name,
+        value: &Expr,
+    ) -> Self::Output {
+        self.resolve_expression(value)?;
+        self.resolve_expression(object)
+    }
+
+    fn visit_this(
+        &mut self,
+        keyword: &Name,
+        depth: &Depth,
+    ) -> Self::Output {
+        if self.current_class == ClassKind::None {
+            return Err(ResolveError::ThisOutsideClass {
+                span: keyword.span(),
+            });
+        }
+        self.resolve_local(keyword, depth);
+        Ok(())
+    }
+
+    fn visit_super(
+        &mut self,
+        keyword: &Name,
+        _method: &Name,
+        depth: &Depth,
+    ) -> Self::Output {
+        match self.current_class {
+            ClassKind::None => {
+                return Err(ResolveError::SuperOutsideClass {
+                    span: keyword.span(),
+                })
+            }
+            ClassKind::Class => {
+                return Err(ResolveError::SuperWithoutSuperclass {
+                    span: keyword.span(),
+                })
+            }
+            ClassKind::Subclass => (),
+        }
+        self.resolve_local(keyword, depth);
+        Ok(())
+    }
+
+    fn visit_call(
+        &mut self,
+        callee: &Expr,
+        _paren: Span,
+        arguments: &[Expr],
+    ) -> Self::Output {
+        self.resolve_expression(callee)?;
+        arguments
+            .iter()
+            .try_for_each(|argument| self.resolve_expression(argument))
+    }
+
+    fn visit_string_literal(
+        &mut self,
+        _value: &Rc<str>,
+    ) -> Self::Output {
+        Ok(())
+    }
+
+    fn visit_boolean_literal(
+        &mut self,
+        _value: bool,
+    ) -> Self::Output {
+        Ok(())
+    }
+
+    fn visit_none_literal(&mut self) -> Self::Output {
+        Ok(())
+    }
+
+    fn visit_number_literal(
+        &mut self,
+        _value: f64,
+    ) -> Self::Output {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use itertools::Itertools;
+
+    use crate::ast::Expr;
+    use crate::ast::Stmt;
+    use crate::error::ResolveError;
+    use crate::lexer::Lexer;
+    use crate::parser::Parser;
+    use crate::resolver::Resolver;
+    use crate::span::Span;
+
+    fn resolve(source: &str) -> Result<Vec<Stmt>, ResolveError> {
+        let statements = Parser::new(Lexer::new(source).flatten()).parse().unwrap();
+        Resolver::resolve(&statements)?;
+        Ok(statements)
+    }
+
+    #[test]
+    fn test_resolving_records_scope_depths_of_locals_only() {
+        let statements = resolve("var global;\n{ var a; { a; global; } }").unwrap();
+        let Stmt::Block {
+            statements: outer, ..
+        } = &statements[1]
+        else {
+            panic!("expected a block")
+        };
+        let Stmt::Block {
+            statements: inner, ..
+        } = &outer[1]
+        else {
+            panic!("expected a block")
+        };
+        let depths = inner
+            .iter()
+            .map(|statement| match statement {
+                Stmt::Expression {
+                    expression: Expr::Variable { depth, .. },
+                    ..
+                } => depth.get(),
+                _ => panic!("expected a variable expression"),
+            })
+            .collect_vec();
+        assert_eq!(depths, vec![Some(1), None]);
+    }
+
+    #[test]
+    fn test_reading_local_in_its_own_initializer_fails() {
+        assert!(matches!(
+            resolve("var a = 1;\n{ var a = a; }"),
+            Err(ResolveError::ReadLocalInOwnInitializer { span: Span { line: 2, .. }, name }) if name == "a"
+        ));
+        assert!(resolve("var a = a;").is_ok());
+    }
+
+    #[test]
+    fn test_duplicate_locals_fail() {
+        assert!(matches!(
+            resolve("fun f(a) { var a; }"),
+            Err(ResolveError::DuplicateLocal { span: Span { line: 1, .. }, name, previous })
+                if name == "a" && previous == Span::new(6, 7, 1, 7)
+        ));
+        assert!(resolve("var a; var a;").is_ok());
+    }
+
+    #[test]
+    fn test_invalid_returns_fail() {
+        assert!(matches!(
+            resolve("return 1;"),
+            Err(ResolveError::ReturnAtTopLevel {
+                span: Span { line: 1, .. }
+            })
+        ));
+        assert!(matches!(
+            resolve("class A { init() { return 1; } }"),
+            Err(ResolveError::ReturnValueFromInitializer {
+                span: Span { line: 1, .. }
+            })
+        ));
+        assert!(resolve("class A { init() { return; } }").is_ok());
+    }
+
+    #[test]
+    fn test_this_and_super_outside_of_classes_fail() {
+        assert!(matches!(
+            resolve("fun f() { this; }"),
+            Err(ResolveError::ThisOutsideClass {
+                span: Span { line: 1, .. }
+            })
+        ));
+        assert!(matches!(
+            resolve("super.method();"),
+            Err(ResolveError::SuperOutsideClass {
+                span: Span { line: 1, .. }
+            })
+        ));
+        assert!(matches!(
+            resolve("class A { m() { super.m(); } }"),
+            Err(ResolveError::SuperWithoutSuperclass {
+                span: Span { line: 1, .. }
+            })
+        ));
+    }
+}
@@ -1,40 +1,140 @@
 use std::io;
 use std::io::Write;
+use std::path::Path;
+use std::rc::Rc;
 
-use itertools::Itertools;
-
+use crate::ast::Stmt;
+use crate::dump;
+use crate::dump::DumpFormat;
 use crate::error::Error;
+use crate::error::LexicalErrors;
+use crate::error::ParseErrors;
 use crate::interpreter::Interpreter;
+use crate::io::read_source_file;
 use crate::lexer::Lexer;
+use crate::lox::Lox;
 use crate::parser::Parser;
+use crate::resolver::Resolver;
+use crate::source::Source;
 
 pub fn run_prompt() -> Result<(), Error> {
-    let mut buf = String::new();
+    let mut lox = Lox::new();
     loop {
         let mut lock = io::stdout().lock();
         lock.write_all(b"> ")?;
         lock.flush()?;
         drop(lock);
+        let mut buf = String::new();
         let bytes_read = io::stdin().read_line(&mut buf)?;
         if bytes_read == 0 {
             return Ok(());
         }
-        match run(&buf) {
-            Ok(o) => {
-                println!("{o}");
-            }
-            Err(e) => {
-                println!("{e}");
-            }
+        if let Err(e) = lox.run("<stdin>", &buf) {
+            println!("{}", e.report());
         }
-        buf.clear();
     }
 }
 
-fn run(buf: &str) -> Result<String, Error> {
-    let tokens = Lexer::lex(buf);
-    let mut parser = Parser::new(tokens.into_iter().flatten().collect_vec());
-    let ast = parser.parse()?;
-    let output = Interpreter::interpret(ast)?;
-    Ok(output)
+pub fn run_file(path: &Path) -> Result<(), Error> {
+    let source = Source::new(path.display().to_string(), read_source_file(path)?);
+    run(&Rc::new(source), &mut Interpreter::new())
+}
+
+/// Returns the tokens of the file at `path`, formatted as `format`.
+pub fn dump_tokens(
+    path: &Path,
+    format: DumpFormat,
+) -> Result<String, Error> {
+    let source = read_source_file(path)?;
+    dump::tokens(&source, format)
+        .map_err(|error| Error::from(error).in_source(path.display().to_string(), source.as_str()))
+}
+
+/// Returns the syntax tree of the file at `path`, formatted as `format`.
+pub fn dump_ast(
+    path: &Path,
+    format: DumpFormat,
+) -> Result<String, Error> {
+    let source = read_source_file(path)?;
+    parse(&source)
+        .map(|statements| dump::ast(&statements, format))
+        .map_err(|error| error.in_source(path.display().to_string(), source.as_str()))
+}
+
+/// Runs `source`, reporting errors against the source they were found in.
+pub(crate) fn run(
+    source: &Rc<Source>,
+    interpreter: &mut Interpreter,
+) -> Result<(), Error> {
+    let statements = parse(&source.code)
+        .and_then(|statements| {
+            Resolver::resolve(&statements)?;
+            Ok(statements)
+        })
+        .map_err(|error| error.in_source(source.name.as_str(), source.code.as_str()))?;
+    interpreter.interpret_source(&statements, source)
+}
+
+fn parse(buf: &str) -> Result<Vec<Stmt>, Error> {
+    // Tokens are streamed into the parser while lexical errors are set aside, so that all of them
+    // are reported rather than the syntax errors they cause.
+    let mut lexical_errors = vec![];
+    let tokens = Lexer::new(buf)
+        .filter_map(|result| result.map_err(|error| lexical_errors.push(error)).ok());
+    let parsed = Parser::new(tokens).parse();
+    if !lexical_errors.is_empty() {
+        return Err(LexicalErrors::new(lexical_errors).into());
+    }
+    Ok(parsed.map_err(ParseErrors::new)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The exit code running `code` fails with, and the error that caused it.
+    fn error(code: &str) -> (u8, Error) {
+        let source = Rc::new(Source::new("test.lox", code));
+        let error = run(&source, &mut Interpreter::with_output(io::sink())).unwrap_err();
+        let exit_code = error.exit_code();
+        match error {
+            Error::InSource { error, .. } => (exit_code, *error),
+            error => panic!("expected the error to carry its source, got {error:?}"),
+        }
+    }
+
+    #[test]
+    fn lexical_errors_exit_with_65() {
+        let (exit_code, cause) = error("print @;");
+        assert!(matches!(cause, Error::Lex(_)));
+        assert_eq!(exit_code, 65);
+    }
+
+    #[test]
+    fn parse_errors_exit_with_65() {
+        let (exit_code, cause) = error("print (1;");
+        assert!(matches!(cause, Error::Pase(_)));
+        assert_eq!(exit_code, 65);
+    }
+
+    #[test]
+    fn resolve_errors_exit_with_65() {
+        let (exit_code, cause) = error("return 1;");
+        assert!(matches!(cause, Error::Resolve(_)));
+        assert_eq!(exit_code, 65);
+    }
+
+    #[test]
+    fn runtime_errors_exit_with_70() {
+        let (exit_code, cause) = error("print -nil;");
+        assert!(matches!(cause, Error::Interpret(_)));
+        assert_eq!(exit_code, 70);
+    }
+
+    #[test]
+    fn unreadable_files_exit_with_74() {
+        let error = run_file(Path::new("does/not/exist.lox")).unwrap_err();
+        assert!(matches!(error, Error::IO(_)));
+        assert_eq!(error.exit_code(), 74);
+    }
 }
@@ -0,0 +1,18 @@
+/// The code of a program, together with the name its errors are reported under.
+#[derive(Debug)]
+pub(crate) struct Source {
+    pub(crate) name: String,
+    pub(crate) code: String,
+}
+
+impl Source {
+    pub(crate) fn new(
+        name: impl Into<String>,
+        code: impl Into<String>,
+    ) -> Self {
+        Self {
+            name: name.into(),
+            code: code.into(),
+        }
+    }
+}
@@ -0,0 +1,70 @@
+use std::fmt::Display;
+use std::fmt::Formatter;
+
+/// A region of source code: the byte range it covers and the line and column it starts at.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Hash)]
+pub struct Span {
+    /// Byte offset of the first character.
+    pub start: usize,
+    /// Byte offset just past the last character.
+    pub end: usize,
+    /// Line of the first character, starting at 1.
+    pub line: usize,
+    /// Column of the first character in characters, starting at 1.
+    pub column: usize,
+}
+
+impl Span {
+    pub(crate) fn new(
+        start: usize,
+        end: usize,
+        line: usize,
+        column: usize,
+    ) -> Self {
+        Self {
+            start,
+            end,
+            line,
+            column,
+        }
+    }
+
+    /// Returns the smallest span covering both `self` and `other`.
+    pub(crate) fn merge(
+        self,
+        other: Span,
+    ) -> Span {
+        let (first, second) = if self.start <= other.start {
+            (self, other)
+        } else {
+            (other, self)
+        };
+        Span {
+            end: first.end.max(second.end),
+            ..first
+        }
+    }
+}
+
+impl Display for Span {
+    fn fmt(
+        &self,
+        f: &mut Formatter<'_>,
+    ) -> std::fmt::Result {
+        write!(f, "{}:{}", self.line, self.column)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn merging_spans_covers_both_in_either_order() {
+        let first = Span::new(2, 5, 1, 3);
+        let second = Span::new(9, 12, 2, 4);
+        let merged = Span::new(2, 12, 1, 3);
+        assert_eq!(first.merge(second), merged);
+        assert_eq!(second.merge(first), merged);
+    }
+}
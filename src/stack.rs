@@ -0,0 +1,13 @@
+//! Growing the Rust stack while walking deeply nested programs, which the parser, the resolver
+//! and the interpreter do recursively.
+
+/// How much of the stack must be left to keep recursing on it.
+const RED_ZONE: usize = 256 * 1024;
+
+/// The size of the stack segments allocated once the current one runs low.
+const SEGMENT_SIZE: usize = 4 * 1024 * 1024;
+
+/// Runs `f`, on a newly allocated stack segment if the current one is running low.
+pub(crate) fn grow<R>(f: impl FnOnce() -> R) -> R {
+    stacker::maybe_grow(RED_ZONE, SEGMENT_SIZE, f)
+}
@@ -1,45 +1,43 @@
 use crate::error::LexicalError;
-
-#[derive(Debug)]
-pub(crate) struct Tokens<'a>(Vec<LexResult<'a>>);
-
-impl<'a> Tokens<'a> {
-    pub fn new(tokens: Vec<LexResult<'a>>) -> Self {
-        Self(tokens)
-    }
-}
-
-impl<'a> IntoIterator for Tokens<'a> {
-    type IntoIter = std::vec::IntoIter<Self::Item>;
-    type Item = LexResult<'a>;
-
-    fn into_iter(self) -> Self::IntoIter {
-        self.0.into_iter()
-    }
-}
+use crate::span::Span;
 
 pub(crate) type LexResult<'a> = Result<Token<'a>, LexicalError>;
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Copy, Clone, PartialEq)]
 pub(crate) struct Token<'a> {
     kind: TokenKind<'a>,
-    line: usize,
+    span: Span,
 }
 
 impl<'a> Token<'a> {
     pub(crate) fn new(
         kind: TokenKind<'a>,
-        line: usize,
+        span: Span,
     ) -> Self {
-        Self { kind, line }
+        Self { kind, span }
     }
 
     pub(crate) fn kind(&self) -> TokenKind<'a> {
         self.kind
     }
 
-    pub(crate) fn line(&self) -> usize {
-        self.line
+    pub(crate) fn span(&self) -> Span {
+        self.span
+    }
+
+    /// Returns the name of an identifier token, or of the `this` and `super` keywords which are
+    /// bound like variables.
+    ///
+    /// # Panics
+    ///
+    /// If the token is neither.
+    pub(crate) fn identifier(&self) -> &'a str {
+        match self.kind {
+            TokenKind::Identifier { lexeme }
+            | TokenKind::This { lexeme }
+            | TokenKind::Super { lexeme } => lexeme,
+            kind => unreachable!("expected an identifier, got {kind:?}"),
+        }
     }
 }
 
@@ -91,5 +89,66 @@ pub(crate) enum TokenKind<'a> {
     Var { lexeme: &'a str },
     While { lexeme: &'a str },
 
+    // Trivia, only produced by lexers that keep it
+    Whitespace { lexeme: &'a str },
+    Comment { lexeme: &'a str },
+
     Eof,
 }
+
+impl<'a> TokenKind<'a> {
+    /// The name of the kind of token, like `LeftParen` or `Identifier`.
+    pub(crate) fn name(&self) -> &'static str {
+        match self {
+            TokenKind::LeftParen { .. } => "LeftParen",
+            TokenKind::RightParen { .. } => "RightParen",
+            TokenKind::LeftBrace { .. } => "LeftBrace",
+            TokenKind::RightBrace { .. } => "RightBrace",
+            TokenKind::Comma { .. } => "Comma",
+            TokenKind::Dot { .. } => "Dot",
+            TokenKind::Minus { .. } => "Minus",
+            TokenKind::Plus { .. } => "Plus",
+            TokenKind::Semicolon { .. } => "Semicolon",
+            TokenKind::Slash { .. } => "Slash",
+            TokenKind::Star { .. } => "Star",
+            TokenKind::Bang { .. } => "Bang",
+            TokenKind::BangEqual { .. } => "BangEqual",
+            TokenKind::Equal { .. } => "Equal",
+            TokenKind::EqualEqual { .. } => "EqualEqual",
+            TokenKind::Greater { .. } => "Greater",
+            TokenKind::GreaterEqual { .. } => "GreaterEqual",
+            TokenKind::Less { .. } => "Less",
+            TokenKind::LessEqual { .. } => "LessEqual",
+            TokenKind::Identifier { .. } => "Identifier",
+            TokenKind::String { .. } => "String",
+            TokenKind::Number { .. } => "Number",
+            TokenKind::And { .. } => "And",
+            TokenKind::Class { .. } => "Class",
+            TokenKind::Else { .. } => "Else",
+            TokenKind::False { .. } => "False",
+            TokenKind::Fun { .. } => "Fun",
+            TokenKind::For { .. } => "For",
+            TokenKind::If { .. } => "If",
+            TokenKind::Nil { .. } => "Nil",
+            TokenKind::Or { .. } => "Or",
+            TokenKind::Print { .. } => "Print",
+            TokenKind::Return { .. } => "Return",
+            TokenKind::Super { .. } => "Super",
+            TokenKind::This { .. } => "This",
+            TokenKind::True { .. } => "True",
+            TokenKind::Var { .. } => "Var",
+            TokenKind::While { .. } => "While",
+            TokenKind::Whitespace { .. } => "Whitespace",
+            TokenKind::Comment { .. } => "Comment",
+            TokenKind::Eof => "Eof",
+        }
+    }
+
+    /// Whether the token is whitespace or a comment, which carry no meaning for the parser.
+    pub(crate) fn is_trivia(&self) -> bool {
+        matches!(
+            self,
+            TokenKind::Whitespace { .. } | TokenKind::Comment { .. }
+        )
+    }
+}
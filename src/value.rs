@@ -0,0 +1,164 @@
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::fmt::Display;
+use std::fmt::Formatter;
+use std::rc::Rc;
+
+use itertools::Itertools;
+
+use crate::class::Class;
+use crate::class::Instance;
+use crate::function::Function;
+use crate::native::NativeFunction;
+
+/// A value produced while running a Lox program.
+///
+/// Functions, classes and instances can be passed around and printed, but their contents are
+/// only accessible from Lox code. Lists and maps are only created by Rust code, for example when
+/// converting with [`IntoLox`](crate::IntoLox); Lox code can store, pass, compare and print them.
+#[derive(Debug, Clone)]
+pub enum Value {
+    Number(f64),
+    String(Rc<str>),
+    Boolean(bool),
+    None,
+    Function(Rc<Function>),
+    NativeFunction(Rc<NativeFunction>),
+    Class(Rc<Class>),
+    Instance(Rc<RefCell<Instance>>),
+    List(Rc<[Value]>),
+    Map(Rc<BTreeMap<String, Value>>),
+}
+
+impl Value {
+    /// Lox's notion of truthiness: `nil` and `false` are falsey, every other value is truthy.
+    pub(crate) fn is_truthy(&self) -> bool {
+        !matches!(self, Value::None | Value::Boolean(false))
+    }
+
+    /// The name of the type of the value, as used in error messages.
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Value::Number(_) => "number",
+            Value::String(_) => "string",
+            Value::Boolean(_) => "boolean",
+            Value::None => "nil",
+            Value::Function(_) | Value::NativeFunction(_) => "function",
+            Value::Class(_) => "class",
+            Value::Instance(_) => "instance",
+            Value::List(_) => "list",
+            Value::Map(_) => "map",
+        }
+    }
+}
+
+/// Lox equality: `nil` only equals itself, numbers, strings and booleans compare by value, lists
+/// and maps by their contents, and functions, classes and instances by identity. Values of
+/// different types are never equal.
+impl PartialEq for Value {
+    fn eq(
+        &self,
+        other: &Self,
+    ) -> bool {
+        match (self, other) {
+            (Value::Number(l), Value::Number(r)) => l == r,
+            (Value::String(l), Value::String(r)) => l == r,
+            (Value::Boolean(l), Value::Boolean(r)) => l == r,
+            (Value::None, Value::None) => true,
+            (Value::Function(l), Value::Function(r)) => Rc::ptr_eq(l, r),
+            (Value::NativeFunction(l), Value::NativeFunction(r)) => Rc::ptr_eq(l, r),
+            (Value::Class(l), Value::Class(r)) => Rc::ptr_eq(l, r),
+            (Value::Instance(l), Value::Instance(r)) => Rc::ptr_eq(l, r),
+            (Value::List(l), Value::List(r)) => l == r,
+            (Value::Map(l), Value::Map(r)) => l == r,
+            _ => false,
+        }
+    }
+}
+
+impl Display for Value {
+    fn fmt(
+        &self,
+        f: &mut Formatter<'_>,
+    ) -> std::fmt::Result {
+        let v = match self {
+            Value::Number(n) => n.to_string(),
+            Value::String(s) => s.to_string(),
+            Value::Boolean(b) => b.to_string(),
+            Value::None => "Nil".to_string(),
+            Value::Function(function) => function.to_string(),
+            Value::NativeFunction(function) => function.to_string(),
+            Value::Class(class) => class.to_string(),
+            Value::Instance(instance) => instance.borrow().to_string(),
+            Value::List(items) => format!("[{}]", items.iter().join(", ")),
+            Value::Map(entries) => format!(
+                "{{{}}}",
+                entries
+                    .iter()
+                    .map(|(key, value)| format!("{key}: {value}"))
+                    .join(", ")
+            ),
+        };
+        write!(f, "{v}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use super::*;
+    use crate::native;
+
+    #[test]
+    fn scalars_compare_by_value() {
+        assert_eq!(Value::Number(1.0), Value::Number(1.0));
+        assert_ne!(Value::Number(1.0), Value::Number(2.0));
+        assert_eq!(Value::String("a".into()), Value::String("a".into()));
+        assert_eq!(Value::Boolean(true), Value::Boolean(true));
+        assert_ne!(Value::Boolean(true), Value::Boolean(false));
+        assert_eq!(Value::None, Value::None);
+    }
+
+    #[test]
+    fn values_of_different_types_are_not_equal() {
+        assert_ne!(Value::None, Value::Boolean(false));
+        assert_ne!(Value::Number(1.0), Value::String("1".into()));
+        assert_ne!(Value::List(Rc::new([])), Value::Map(Rc::default()));
+    }
+
+    #[test]
+    fn nan_is_not_equal_to_itself() {
+        assert_ne!(Value::Number(f64::NAN), Value::Number(f64::NAN));
+    }
+
+    #[test]
+    fn functions_compare_by_identity() {
+        let clock = Rc::new(native::clock());
+        assert_eq!(
+            Value::NativeFunction(clock.clone()),
+            Value::NativeFunction(clock)
+        );
+        assert_ne!(
+            Value::NativeFunction(Rc::new(native::clock())),
+            Value::NativeFunction(Rc::new(native::clock()))
+        );
+    }
+
+    #[test]
+    fn lists_and_maps_compare_by_contents() {
+        let list = |items: &[f64]| Value::List(items.iter().copied().map(Value::Number).collect());
+        assert_eq!(list(&[1.0, 2.0]), list(&[1.0, 2.0]));
+        assert_ne!(list(&[1.0, 2.0]), list(&[2.0, 1.0]));
+        assert_ne!(list(&[1.0]), list(&[1.0, 1.0]));
+
+        let map = |value: f64| {
+            Value::Map(Rc::new(BTreeMap::from([(
+                "a".to_string(),
+                Value::Number(value),
+            )])))
+        };
+        assert_eq!(map(1.0), map(1.0));
+        assert_ne!(map(1.0), map(2.0));
+    }
+}
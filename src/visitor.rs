@@ -0,0 +1,240 @@
+use std::rc::Rc;
+
+use crate::ast::ClassDecl;
+use crate::ast::Depth;
+use crate::ast::Expr;
+use crate::ast::FunctionDecl;
+use crate::ast::Name;
+use crate::ast::Operator;
+use crate::ast::Stmt;
+use crate::span::Span;
+
+/// A pass over expressions, with one method per kind of expression. [`Expr::accept`] calls the
+/// method matching the expression, passing it the expression's parts.
+pub(crate) trait ExprVisitor {
+    type Output;
+
+    fn visit_binary(
+        &mut self,
+        left: &Expr,
+        operator: &Operator,
+        right: &Expr,
+    ) -> Self::Output;
+
+    fn visit_unary(
+        &mut self,
+        operator: &Operator,
+        right: &Expr,
+    ) -> Self::Output;
+
+    fn visit_logical(
+        &mut self,
+        left: &Expr,
+        operator: &Operator,
+        right: &Expr,
+    ) -> Self::Output;
+
+    fn visit_grouping(
+        &mut self,
+        expression: &Expr,
+    ) -> Self::Output;
+
+    fn visit_variable(
+        &mut self,
+        name: &Name,
+        depth: &Depth,
+    ) -> Self::Output;
+
+    fn visit_assign(
+        &mut self,
+        name: &Name,
+        value: &Expr,
+        depth: &Depth,
+    ) -> Self::Output;
+
+    fn visit_get(
+        &mut self,
+        object: &Expr,
+        name: &Name,
+    ) -> Self::Output;
+
+    fn visit_set(
+        &mut self,
+        object: &Expr,
+        name: &Name,
+        value: &Expr,
+    ) -> Self::Output;
+
+    fn visit_this(
+        &mut self,
+        keyword: &Name,
+        depth: &Depth,
+    ) -> Self::Output;
+
+    fn visit_super(
+        &mut self,
+        keyword: &Name,
+        method: &Name,
+        depth: &Depth,
+    ) -> Self::Output;
+
+    fn visit_call(
+        &mut self,
+        callee: &Expr,
+        paren: Span,
+        arguments: &[Expr],
+    ) -> Self::Output;
+
+    fn visit_string_literal(
+        &mut self,
+        value: &Rc<str>,
+    ) -> Self::Output;
+
+    fn visit_boolean_literal(
+        &mut self,
+        value: bool,
+    ) -> Self::Output;
+
+    fn visit_none_literal(&mut self) -> Self::Output;
+
+    fn visit_number_literal(
+        &mut self,
+        value: f64,
+    ) -> Self::Output;
+}
+
+/// A pass over statements, with one method per kind of statement. [`Stmt::accept`] calls the
+/// method matching the statement, passing it the statement's parts.
+pub(crate) trait StmtVisitor {
+    type Output;
+
+    fn visit_expression_statement(
+        &mut self,
+        expression: &Expr,
+    ) -> Self::Output;
+
+    fn visit_print(
+        &mut self,
+        expression: &Expr,
+        span: Span,
+    ) -> Self::Output;
+
+    fn visit_var(
+        &mut self,
+        name: &Name,
+        initializer: Option<&Expr>,
+    ) -> Self::Output;
+
+    fn visit_block(
+        &mut self,
+        statements: &[Stmt],
+    ) -> Self::Output;
+
+    fn visit_if(
+        &mut self,
+        condition: &Expr,
+        then_branch: &Stmt,
+        else_branch: Option<&Stmt>,
+    ) -> Self::Output;
+
+    fn visit_while(
+        &mut self,
+        condition: &Expr,
+        body: &Stmt,
+    ) -> Self::Output;
+
+    fn visit_function(
+        &mut self,
+        declaration: &Rc<FunctionDecl>,
+    ) -> Self::Output;
+
+    fn visit_class(
+        &mut self,
+        declaration: &ClassDecl,
+    ) -> Self::Output;
+
+    fn visit_return(
+        &mut self,
+        keyword: Span,
+        value: Option<&Expr>,
+    ) -> Self::Output;
+}
+
+impl Expr {
+    pub(crate) fn accept<V>(
+        &self,
+        visitor: &mut V,
+    ) -> V::Output
+    where
+        V: ExprVisitor + ?Sized,
+    {
+        match self {
+            Expr::Binary {
+                left,
+                operator,
+                right,
+            } => visitor.visit_binary(left, operator, right),
+            Expr::Unary { operator, right } => visitor.visit_unary(operator, right),
+            Expr::Logical {
+                left,
+                operator,
+                right,
+            } => visitor.visit_logical(left, operator, right),
+            Expr::Grouping { expression, .. } => visitor.visit_grouping(expression),
+            Expr::Variable { name, depth } => visitor.visit_variable(name, depth),
+            Expr::Assign { name, value, depth } => visitor.visit_assign(name, value, depth),
+            Expr::Get { object, name } => visitor.visit_get(object, name),
+            Expr::Set {
+                object,
+                name,
+                value,
+            } => visitor.visit_set(object, name, value),
+            Expr::This { keyword, depth } => visitor.visit_this(keyword, depth),
+            Expr::Super {
+                keyword,
+                method,
+                depth,
+            } => visitor.visit_super(keyword, method, depth),
+            Expr::Call {
+                callee,
+                paren,
+                arguments,
+            } => visitor.visit_call(callee, *paren, arguments),
+            Expr::StringLiteral { value, .. } => visitor.visit_string_literal(value),
+            Expr::BooleanLiteral { value, .. } => visitor.visit_boolean_literal(*value),
+            Expr::NoneLiteral { .. } => visitor.visit_none_literal(),
+            Expr::NumberLiteral { value, .. } => visitor.visit_number_literal(*value),
+        }
+    }
+}
+
+impl Stmt {
+    pub(crate) fn accept<V>(
+        &self,
+        visitor: &mut V,
+    ) -> V::Output
+    where
+        V: StmtVisitor + ?Sized,
+    {
+        match self {
+            Stmt::Expression { expression, .. } => visitor.visit_expression_statement(expression),
+            Stmt::Print { expression, span } => visitor.visit_print(expression, *span),
+            Stmt::Var {
+                name, initializer, ..
+            } => visitor.visit_var(name, initializer.as_ref()),
+            Stmt::Block { statements, .. } => visitor.visit_block(statements),
+            Stmt::If {
+                condition,
+                then_branch,
+                else_branch,
+                ..
+            } => visitor.visit_if(condition, then_branch, else_branch.as_deref()),
+            Stmt::While {
+                condition, body, ..
+            } => visitor.visit_while(condition, body),
+            Stmt::Function(declaration) => visitor.visit_function(declaration),
+            Stmt::Class(declaration) => visitor.visit_class(declaration),
+            Stmt::Return { keyword, value, .. } => visitor.visit_return(*keyword, value.as_ref()),
+        }
+    }
+}